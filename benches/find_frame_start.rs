@@ -0,0 +1,35 @@
+//! Compares `find_frame_start` with a naive scan for the header sentinel,
+//! run with `cargo bench --bench find_frame_start`
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+use lmcp_sentinelizer::LmcpSentinelizer;
+
+const SENTINEL: &[u8] = b"+=+=+=+=";
+
+fn naive_find(data: &[u8]) -> Option<usize> {
+    data.windows(SENTINEL.len()).position(|w| w == SENTINEL)
+}
+
+fn bench_find_frame_start(c: &mut Criterion) {
+    // 1 MB of payload-like bytes with lots of partial sentinel matches,
+    // followed by a frame
+    let mut data: Vec<u8> = b"+=+-x".iter().cycle().take(1 << 20).copied().collect();
+    let offset = data.len();
+    data.extend_from_slice(&LmcpSentinelizer::create_sentinelized_stream(b"payload"));
+
+    assert_eq!(naive_find(&data), Some(offset));
+    assert_eq!(LmcpSentinelizer::find_frame_start(&data), Some(offset));
+
+    let mut group = c.benchmark_group("find_frame_start");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("naive", |b| b.iter(|| naive_find(black_box(&data))));
+    group.bench_function("find_frame_start", |b| {
+        b.iter(|| LmcpSentinelizer::find_frame_start(black_box(&data)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_find_frame_start);
+criterion_main!(benches);
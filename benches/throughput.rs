@@ -0,0 +1,74 @@
+//! Encode and parse throughput for small, medium and large payloads,
+//! run with `cargo bench --bench throughput`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+use lmcp_sentinelizer::{LmcpSentinelizer, SentinelDecoder};
+
+/// Payload sizes: a short LMCP message, a typical one and a large one
+const SIZES: [(&str, usize); 3] = [("25B", 25), ("4KB", 4 * 1024), ("1MB", 1024 * 1024)];
+
+/// Chunk size the decoder is fed in, as read from a socket
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|x| (x % 251) as u8).collect()
+}
+
+fn bench_create(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create_sentinelized_stream");
+    for &(name, len) in &SIZES {
+        let payload = payload(len);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &payload, |b, payload| {
+            b.iter(|| LmcpSentinelizer::create_sentinelized_stream(black_box(payload)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_sentinelized_stream");
+    for &(name, len) in &SIZES {
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&payload(len));
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &frame, |b, frame| {
+            b.iter_batched(
+                || frame.clone(),
+                |frame| LmcpSentinelizer::parse_sentinelized_stream(black_box(frame)).unwrap(),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_decoder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SentinelDecoder");
+    for &(name, len) in &SIZES {
+        // enough frames for a few reads even for the small payloads
+        let count = (4 * READ_CHUNK_LEN / len).max(1);
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&payload(len));
+        let data = frame.repeat(count);
+        group.throughput(Throughput::Bytes((len * count) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &data, |b, data| {
+            b.iter(|| {
+                let mut decoder = SentinelDecoder::new();
+                let mut decoded = 0;
+                for chunk in data.chunks(READ_CHUNK_LEN) {
+                    decoder.push_bytes(black_box(chunk));
+                    while let Some(msg) = decoder.next_message() {
+                        black_box(msg.unwrap());
+                        decoded += 1;
+                    }
+                }
+                assert_eq!(decoded, count);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_create, bench_parse, bench_decoder);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+/* Round trips a payload through the C interface, see the FFI section of README.md */
+#include <stdio.h>
+#include <string.h>
+
+#include "lmcp_sentinelizer.h"
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,      \
+                    __LINE__, #cond);                                   \
+            return 1;                                                   \
+        }                                                               \
+    } while (0)
+
+int main(void)
+{
+    const char *payload = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+    const char *expected = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    size_t payload_len = strlen(payload);
+    uint8_t frame[128];
+    uint8_t decoded[128];
+    size_t frame_len = 0;
+    size_t decoded_len = 0;
+    size_t consumed = 0;
+
+    /* query the size first */
+    CHECK(lmcp_sentinel_encode((const uint8_t *)payload, payload_len, NULL, 0, &frame_len)
+          == LMCP_SENTINEL_BUFFER_TOO_SMALL);
+    CHECK(frame_len == strlen(expected));
+    CHECK(lmcp_sentinel_encoded_len((const uint8_t *)payload, payload_len) == frame_len);
+
+    CHECK(lmcp_sentinel_encode((const uint8_t *)payload, payload_len, frame, sizeof(frame),
+                               &frame_len) == LMCP_SENTINEL_OK);
+    CHECK(memcmp(frame, expected, frame_len) == 0);
+
+    /* a partial frame asks for more bytes */
+    CHECK(lmcp_sentinel_decode(frame, frame_len - 1, decoded, sizeof(decoded), &decoded_len,
+                               &consumed) == LMCP_SENTINEL_INCOMPLETE);
+
+    CHECK(lmcp_sentinel_decode(frame, frame_len, decoded, sizeof(decoded), &decoded_len,
+                               &consumed) == LMCP_SENTINEL_OK);
+    CHECK(decoded_len == payload_len);
+    CHECK(consumed == frame_len);
+    CHECK(memcmp(decoded, payload, payload_len) == 0);
+
+    /* corrupt the checksum */
+    frame[frame_len - 9] = '6';
+    CHECK(lmcp_sentinel_decode(frame, frame_len, decoded, sizeof(decoded), &decoded_len,
+                               &consumed) == LMCP_SENTINEL_CHECKSUM_ERROR);
+
+    printf("ffi round trip OK\n");
+    return 0;
+}
@@ -0,0 +1,23 @@
+//! Feed arbitrary bytes to the parsers, they may return errors but must never panic,
+//! and every input has to survive a round trip as a payload
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lmcp_sentinelizer::{LmcpSentinelizer, SentinelDecoder};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = LmcpSentinelizer::parse_sentinelized_stream(data.to_vec());
+    let _ = LmcpSentinelizer::parse_frame(data);
+    let _ = LmcpSentinelizer::parse_all(data);
+    let _ = LmcpSentinelizer::parse_resync(data);
+    let _ = LmcpSentinelizer::parse_stream(data.to_vec());
+    assert_eq!(
+        LmcpSentinelizer::round_trip(data).as_deref(),
+        Ok(data),
+        "payload doesn't survive encoding"
+    );
+
+    let mut decoder = SentinelDecoder::new();
+    decoder.push_bytes(data);
+    while decoder.next_message().is_some() {}
+});
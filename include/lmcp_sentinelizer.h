@@ -0,0 +1,136 @@
+#ifndef LMCP_SENTINELIZER_H
+#define LMCP_SENTINELIZER_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The call succeeded
+ */
+#define LMCP_SENTINEL_OK 0
+
+/**
+ * A pointer argument was null
+ */
+#define LMCP_SENTINEL_NULL_POINTER -1
+
+/**
+ * The output buffer is too small, `out_len` holds the required size
+ */
+#define LMCP_SENTINEL_BUFFER_TOO_SMALL -2
+
+/**
+ * The input is an incomplete frame, more bytes are needed
+ */
+#define LMCP_SENTINEL_INCOMPLETE -3
+
+/**
+ * See `Error::SentinelNotFound`
+ */
+#define LMCP_SENTINEL_SENTINEL_NOT_FOUND -4
+
+/**
+ * See `Error::ChecksumVerifyError`
+ */
+#define LMCP_SENTINEL_CHECKSUM_ERROR -5
+
+/**
+ * See `Error::InvalidNumericField`
+ */
+#define LMCP_SENTINEL_INVALID_NUMERIC_FIELD -6
+
+/**
+ * See `Error::LengthMismatch`
+ */
+#define LMCP_SENTINEL_LENGTH_MISMATCH -7
+
+/**
+ * See `Error::PayloadTooLarge`
+ */
+#define LMCP_SENTINEL_PAYLOAD_TOO_LARGE -8
+
+/**
+ * See `Error::WhitespaceInNumericField`
+ */
+#define LMCP_SENTINEL_WHITESPACE_IN_NUMERIC_FIELD -9
+
+/**
+ * See `Error::TrailingBytes`
+ */
+#define LMCP_SENTINEL_TRAILING_BYTES -10
+
+/**
+ * See `Error::MalformedFrame`
+ */
+#define LMCP_SENTINEL_MALFORMED_FRAME -11
+
+/**
+ * See `Error::ResyncLimitExceeded`
+ */
+#define LMCP_SENTINEL_RESYNC_LIMIT_EXCEEDED -12
+
+/**
+ * See `Error::UnexpectedEof`
+ */
+#define LMCP_SENTINEL_UNEXPECTED_EOF -13
+
+/**
+ * See `Error::MisplacedChecksumSentinel`
+ */
+#define LMCP_SENTINEL_MISPLACED_CHECKSUM_SENTINEL -14
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Number of bytes `len` bytes of payload at `data` occupy once sentinelized
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes, it may be null if `len` is 0.
+ */
+size_t lmcp_sentinel_encoded_len(const uint8_t *data, size_t len);
+
+/**
+ * Sentinelize the `len` bytes of payload at `data` into the `out_cap` bytes at `out`.
+ * The frame length is stored in `out_len`, also when `out` is too small, so the
+ * required size can be queried by passing a capacity of 0.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` to `out_cap` writable bytes,
+ * either may be null if its length is 0. `out_len` must point to a writable `size_t`.
+ */
+int32_t lmcp_sentinel_encode(const uint8_t *data,
+                             size_t len,
+                             uint8_t *out,
+                             size_t out_cap,
+                             size_t *out_len);
+
+/**
+ * Decode the frame at the beginning of the `len` bytes at `data`, copy its payload
+ * into the `out_cap` bytes at `out` and store the payload length in `out_len` and
+ * the number of bytes the frame occupies in `consumed`. On
+ * `LMCP_SENTINEL_INCOMPLETE` the caller should retry once more bytes arrived.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` to `out_cap` writable bytes,
+ * either may be null if its length is 0. `out_len` and `consumed` must point to
+ * writable `size_t`s.
+ */
+int32_t lmcp_sentinel_decode(const uint8_t *data,
+                             size_t len,
+                             uint8_t *out,
+                             size_t out_cap,
+                             size_t *out_len,
+                             size_t *consumed);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LMCP_SENTINELIZER_H */
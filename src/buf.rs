@@ -0,0 +1,89 @@
+//! Integration with the `bytes` crate for zero-copy networking pipelines
+
+use bytes::{Bytes, BytesMut};
+
+use super::config::FrameRanges;
+use super::{Error, LmcpSentinelizer, Result, Sentinelizer};
+
+impl LmcpSentinelizer {
+    /// Add sentinel strings to the payload, return the frame as `Bytes`
+    pub fn create_sentinelized_bytes(data: &[u8]) -> Bytes {
+        Sentinelizer::default().create_sentinelized_bytes(data)
+    }
+
+    /// Process the frame at the beginning of the buffer and advance the buffer past it.
+    /// Return `Ok(None)` and leave the buffer untouched when the frame is incomplete.
+    /// The returned payload shares the memory of the buffer.
+    pub fn parse_sentinelized_bytes(data: &mut BytesMut) -> Result<Option<Bytes>> {
+        Sentinelizer::default().parse_sentinelized_bytes(data)
+    }
+}
+
+impl Sentinelizer {
+    /// Add sentinel strings to the payload, return the frame as `Bytes`
+    pub fn create_sentinelized_bytes(&self, data: &[u8]) -> Bytes {
+        Bytes::from(self.create_sentinelized_stream(data))
+    }
+
+    /// Process the frame at the beginning of the buffer and advance the buffer past it,
+    /// see `LmcpSentinelizer::parse_sentinelized_bytes`
+    pub fn parse_sentinelized_bytes(&self, data: &mut BytesMut) -> Result<Option<Bytes>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        match self.locate_frame(data) {
+            Ok(FrameRanges { payload, frame_len }) => {
+                let frame = data.split_to(frame_len).freeze();
+                Ok(Some(frame.slice(payload)))
+            }
+            Err(Error::Incomplete { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SentinelKind;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let frame = LmcpSentinelizer::create_sentinelized_bytes(TEST_PAYLOAD.as_bytes());
+        assert_eq!(frame, TEST_DATA.as_bytes());
+
+        let mut data = BytesMut::new();
+        data.extend_from_slice(&frame);
+        data.extend_from_slice(&frame[..20]);
+
+        let payload = LmcpSentinelizer::parse_sentinelized_bytes(&mut data).unwrap();
+        assert_eq!(payload, Some(Bytes::from_static(TEST_PAYLOAD.as_bytes())));
+        assert_eq!(data.len(), 20);
+
+        // the partial frame stays in the buffer
+        assert_eq!(
+            LmcpSentinelizer::parse_sentinelized_bytes(&mut data),
+            Ok(None)
+        );
+        assert_eq!(data.len(), 20);
+
+        data.extend_from_slice(&frame[20..]);
+        let payload = LmcpSentinelizer::parse_sentinelized_bytes(&mut data).unwrap();
+        assert_eq!(payload, Some(Bytes::from_static(TEST_PAYLOAD.as_bytes())));
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn test_bytes_corrupt_frame() {
+        let mut data = BytesMut::from("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
+        assert_eq!(
+            LmcpSentinelizer::parse_sentinelized_bytes(&mut data),
+            Err(Error::SentinelNotFound {
+                which: SentinelKind::BeforePayloadSize
+            })
+        );
+    }
+}
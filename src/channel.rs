@@ -0,0 +1,130 @@
+//! Decoding of payloads from the byte chunks received over an mpsc channel, e.g.
+//! from a thread that owns the socket, the channel counterpart of `FrameReader`
+
+use std::sync::mpsc::Receiver;
+
+use super::{Result, SentinelDecoder, Sentinelizer};
+
+/// Receives chunks of sentinelized bytes from a channel and returns the decoded
+/// payloads, in order.
+///
+/// The channel is only received from when the buffered bytes don't contain another
+/// frame, blocking until the next chunk arrives. Corrupt frames are returned as
+/// errors and skipped. Once all senders are dropped, a partial frame is reported as
+/// `Error::Incomplete`, after which the receiver is exhausted.
+#[derive(Debug)]
+pub struct FrameReceiver {
+    receiver: Receiver<Vec<u8>>,
+    decoder: SentinelDecoder,
+    disconnected: bool,
+}
+
+impl FrameReceiver {
+    /// Decode frames with the default sentinels from the chunks of the channel
+    pub fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        Self::with_sentinelizer(receiver, Sentinelizer::default())
+    }
+
+    /// Decode frames using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(receiver: Receiver<Vec<u8>>, sentinelizer: Sentinelizer) -> Self {
+        FrameReceiver {
+            receiver,
+            decoder: SentinelDecoder::with_sentinelizer(sentinelizer),
+            disconnected: false,
+        }
+    }
+
+    /// Receive until the next frame is complete and return its payload,
+    /// or `None` once the channel is disconnected on a frame boundary
+    pub fn recv_frame(&mut self) -> Option<Result<Vec<u8>>> {
+        loop {
+            if let Some(msg) = self.decoder.next_message() {
+                return Some(msg);
+            }
+            if self.disconnected {
+                return self.decoder.finish().map(Err);
+            }
+
+            match self.receiver.recv() {
+                Ok(chunk) => self.decoder.push_bytes(&chunk),
+                Err(_) => self.disconnected = true,
+            }
+        }
+    }
+
+    /// Call `f` with each payload until the channel is disconnected, e.g. as the
+    /// body of a decoding thread. Corrupt frames are skipped, `f` only sees payloads.
+    pub fn for_each_payload(mut self, mut f: impl FnMut(Vec<u8>)) {
+        while let Some(msg) = self.recv_frame() {
+            if let Ok(payload) = msg {
+                f(payload);
+            }
+        }
+    }
+
+    /// Return the receiver, dropping any buffered bytes
+    pub fn into_inner(self) -> Receiver<Vec<u8>> {
+        self.receiver
+    }
+}
+
+impl Iterator for FrameReceiver {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv_frame()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Error, LmcpSentinelizer};
+    use std::sync::mpsc;
+    use std::thread;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_fragmented_frames() {
+        let payloads: Vec<Vec<u8>> = vec![TEST_PAYLOAD.into(), b"".to_vec(), vec![0xFF; 1000]];
+        let mut data = Vec::new();
+        for payload in &payloads {
+            data.extend_from_slice(&LmcpSentinelizer::create_sentinelized_stream(payload));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let sender = thread::spawn(move || {
+            for chunk in data.chunks(7) {
+                tx.send(chunk.to_vec()).unwrap();
+            }
+        });
+        let received: Vec<_> = FrameReceiver::new(rx).collect();
+        sender.join().unwrap();
+        let expected: Vec<_> = payloads.into_iter().map(Ok).collect();
+        assert_eq!(received, expected);
+
+        let (tx, rx) = mpsc::channel();
+        tx.send(TEST_DATA.as_bytes()[..40].to_vec()).unwrap();
+        tx.send(TEST_DATA.as_bytes()[40..].to_vec()).unwrap();
+        tx.send(TEST_DATA.as_bytes()[..20].to_vec()).unwrap();
+        drop(tx);
+        let mut payloads = Vec::new();
+        FrameReceiver::new(rx).for_each_payload(|payload| payloads.push(payload));
+        assert_eq!(payloads, vec![TEST_PAYLOAD.as_bytes().to_vec()]);
+    }
+
+    #[test]
+    fn test_partial_frame_at_disconnect() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(TEST_DATA.as_bytes()[..30].to_vec()).unwrap();
+        drop(tx);
+        let mut receiver = FrameReceiver::new(rx);
+        assert_eq!(
+            receiver.recv_frame(),
+            Some(Err(Error::Incomplete { needed: None }))
+        );
+        assert_eq!(receiver.recv_frame(), None);
+    }
+}
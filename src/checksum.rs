@@ -0,0 +1,229 @@
+//! Checksum algorithms for the frame trailer
+
+use super::{Error, LmcpSentinelizer, Result};
+
+/// Checksum algorithm used for the trailer of a frame.
+///
+/// `Sum` is the additive byte sum of `UxAS_SentinelSerialBuffer` and has to be used
+/// to talk to OpenUxAS and OpenAMASE. It is insensitive to the order of the bytes,
+/// so e.g. two swapped payload bytes go unnoticed. `Crc32` catches those, but only
+/// works when both ends are configured to use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Checksum {
+    #[default]
+    Sum,
+    /// CRC-32 (IEEE 802.3) of the payload
+    Crc32,
+}
+
+impl Checksum {
+    /// Calculate checksum over data
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            Checksum::Sum => LmcpSentinelizer::calculate_checksum(data),
+            Checksum::Crc32 => crc32(data),
+        }
+    }
+
+    /// Check the data against an expected checksum
+    pub fn verify(self, data: &[u8], expected: u32) -> Result<()> {
+        if self.compute(data) == expected {
+            Ok(())
+        } else {
+            Err(Error::ChecksumVerifyError)
+        }
+    }
+}
+
+/// Which bytes of a frame the trailer checksum is calculated over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumCoverage {
+    /// Only the payload, as in OpenUxAS
+    #[default]
+    Payload,
+    /// The digits of the length field followed by the payload, as some other
+    /// sentinel framings do
+    LengthAndPayload,
+}
+
+/// Checksum state that is fed the data in pieces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RunningChecksum {
+    checksum: Checksum,
+    state: u32,
+}
+
+impl RunningChecksum {
+    pub(crate) fn new(checksum: Checksum) -> Self {
+        let state = match checksum {
+            Checksum::Sum => 0,
+            Checksum::Crc32 => !0,
+        };
+        RunningChecksum { checksum, state }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.state = match self.checksum {
+            Checksum::Sum => self
+                .state
+                .wrapping_add(LmcpSentinelizer::calculate_checksum(data)),
+            Checksum::Crc32 => crc32_update(self.state, data),
+        };
+    }
+
+    /// Checksum of all the data fed so far
+    pub(crate) fn value(&self) -> u32 {
+        match self.checksum {
+            Checksum::Sum => self.state,
+            Checksum::Crc32 => !self.state,
+        }
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &x| {
+        CRC32_TABLE[((crc ^ u32::from(x)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{SentinelConfig, SentinelDecoder};
+
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(Checksum::Crc32.compute(b"123456789"), 0xCBF4_3926);
+        assert_eq!(Checksum::Crc32.compute(&[]), 0);
+        assert_eq!(Checksum::Sum.compute(TEST_PAYLOAD.as_bytes()), 1925);
+    }
+
+    #[test]
+    fn test_running_checksum() {
+        for &checksum in &[Checksum::Sum, Checksum::Crc32] {
+            let mut running = RunningChecksum::new(checksum);
+            assert_eq!(running.value(), checksum.compute(&[]));
+            for chunk in TEST_PAYLOAD.as_bytes().chunks(7) {
+                running.update(chunk);
+            }
+            assert_eq!(running.value(), checksum.compute(TEST_PAYLOAD.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_crc32_round_trip() {
+        let sentinelizer = SentinelConfig::new()
+            .checksum(Checksum::Crc32)
+            .build()
+            .unwrap();
+        let frame = sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+        let crc = Checksum::Crc32.compute(TEST_PAYLOAD.as_bytes()).to_string();
+        assert!(frame.windows(crc.len()).any(|w| w == crc.as_bytes()));
+        assert_eq!(
+            sentinelizer.encoded_len(TEST_PAYLOAD.as_bytes()),
+            frame.len()
+        );
+
+        let (payload, _) = sentinelizer.parse_one(&frame).unwrap();
+        assert_eq!(payload, TEST_PAYLOAD.as_bytes().to_vec());
+
+        // the default sentinelizer expects the byte sum
+        assert_eq!(
+            SentinelConfig::new().build().unwrap().parse_one(&frame),
+            Err(Error::ChecksumVerifyError)
+        );
+    }
+
+    #[test]
+    fn test_length_and_payload_coverage() {
+        let payload = TEST_PAYLOAD.as_bytes();
+        let payload_only = SentinelConfig::new().build().unwrap();
+        for &checksum in &[Checksum::Sum, Checksum::Crc32] {
+            let sentinelizer = SentinelConfig::new()
+                .checksum(checksum)
+                .checksum_coverage(ChecksumCoverage::LengthAndPayload)
+                .build()
+                .unwrap();
+            let expected = checksum.compute(b"25ABCDEFGHIJKLMNOPQRSTUVWXY");
+            assert_eq!(sentinelizer.checksum(payload), expected);
+
+            let frame = sentinelizer.create_sentinelized_stream(payload);
+            assert_eq!(frame.len(), sentinelizer.encoded_len(payload));
+            assert_eq!(sentinelizer.parse_one(&frame).unwrap().0, payload.to_vec());
+
+            let mut encoder = sentinelizer.encoder();
+            encoder.update(&payload[..10]);
+            encoder.update(&payload[10..]);
+            let mut encoded = Vec::new();
+            encoder.finish_into(&mut encoded);
+            assert_eq!(encoded, frame);
+
+            let mut decoder = SentinelDecoder::with_sentinelizer(sentinelizer.clone());
+            for chunk in frame.chunks(5) {
+                decoder.push_bytes(chunk);
+            }
+            assert_eq!(decoder.next_message(), Some(Ok(payload.to_vec())));
+
+            // a frame made in one mode doesn't verify in the other
+            let other = SentinelConfig::new().checksum(checksum).build().unwrap();
+            assert_eq!(other.parse_one(&frame), Err(Error::ChecksumVerifyError));
+            let frame = other.create_sentinelized_stream(payload);
+            assert_eq!(
+                sentinelizer.parse_one(&frame),
+                Err(Error::ChecksumVerifyError)
+            );
+        }
+        assert_eq!(payload_only.checksum(payload), 1925);
+    }
+
+    #[test]
+    fn test_transposition_detection() {
+        for &checksum in &[Checksum::Sum, Checksum::Crc32] {
+            let sentinelizer = SentinelConfig::new().checksum(checksum).build().unwrap();
+            let mut frame = sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+            // "AB" -> "BA" keeps the byte sum
+            frame.swap(18, 19);
+            let res = sentinelizer.parse_one(&frame);
+            match checksum {
+                Checksum::Sum => assert!(res.is_ok()),
+                Checksum::Crc32 => assert_eq!(res, Err(Error::ChecksumVerifyError)),
+            }
+
+            // a single flipped bit is caught by both
+            frame.swap(18, 19);
+            frame[20] ^= 0x04;
+            assert_eq!(
+                sentinelizer.parse_one(&frame),
+                Err(Error::ChecksumVerifyError)
+            );
+        }
+    }
+}
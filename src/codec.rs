@@ -0,0 +1,123 @@
+//! `tokio_util` codec so the sentinelizer can be used with `Framed`
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::Sentinelizer;
+
+/// Frames outgoing payloads and deframes incoming sentinelized bytes
+///
+/// Corrupt frames are dropped up to the next possible frame header, like
+/// `LmcpSentinelizer::parse_stream` does, so one bad frame doesn't end the `Framed`
+/// stream; with the `tracing` feature each one is reported as an event. The
+/// `io::Error`s of the stream are those of the underlying transport.
+#[derive(Debug, Default, Clone)]
+pub struct SentinelCodec {
+    sentinelizer: Sentinelizer,
+}
+
+impl SentinelCodec {
+    /// Frame payloads with the default sentinels
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Frame payloads using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(sentinelizer: Sentinelizer) -> Self {
+        SentinelCodec { sentinelizer }
+    }
+}
+
+impl Encoder<Vec<u8>> for SentinelCodec {
+    type Error = io::Error;
+
+    /// Write the frame straight into `dst`, which is grown by the frame length once
+    fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), io::Error> {
+        let checksum = self.sentinelizer.checksum(&item);
+        dst.reserve(self.sentinelizer.frame_len(item.len(), checksum));
+        self.sentinelizer
+            .write_to_with_checksum(&item, checksum, &mut dst.writer())
+    }
+}
+
+impl Decoder for SentinelCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    /// Bytes of an incomplete frame are left in `src` until the rest arrives
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, io::Error> {
+        loop {
+            match self.sentinelizer.parse_sentinelized_bytes(src) {
+                Ok(payload) => return Ok(payload.map(|payload| payload.to_vec())),
+                Err(_) => {
+                    let skipped = self.sentinelizer.next_frame_candidate(src);
+                    trace_event!(warn, len = skipped, "dropping a corrupt frame");
+                    src.advance(skipped);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SentinelConfig;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_incomplete_frame_not_consumed() {
+        let mut codec = SentinelCodec::new();
+        let mut src = BytesMut::from(&TEST_DATA.as_bytes()[..40]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert_eq!(src.len(), 40);
+
+        src.extend_from_slice(&TEST_DATA.as_bytes()[40..]);
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(TEST_PAYLOAD.as_bytes().to_vec())
+        );
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_frame_skipped() {
+        let mut codec = SentinelCodec::new();
+        let corrupted = TEST_DATA.replace("1925", "1926");
+        let mut src = BytesMut::from([&corrupted, "junk", TEST_DATA].concat().as_bytes());
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(TEST_PAYLOAD.as_bytes().to_vec())
+        );
+        assert!(src.is_empty());
+
+        // the corrupt frame is dropped even when the next one is still incomplete
+        src.extend_from_slice(corrupted.as_bytes());
+        src.extend_from_slice(&TEST_DATA.as_bytes()[..20]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert_eq!(&src[..], &TEST_DATA.as_bytes()[..20]);
+    }
+
+    #[test]
+    fn test_encode_configured() {
+        let sentinelizer = SentinelConfig::new()
+            .before_payload_size(b"<<")
+            .build()
+            .unwrap();
+        let mut codec = SentinelCodec::with_sentinelizer(sentinelizer.clone());
+        let mut dst = BytesMut::from(&b"previous"[..]);
+        codec
+            .encode(TEST_PAYLOAD.as_bytes().to_vec(), &mut dst)
+            .unwrap();
+        let frame = sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+        assert_eq!(&dst[8..], &frame[..]);
+        assert_eq!(
+            codec.decode(&mut dst.split_off(8)).unwrap(),
+            Some(TEST_PAYLOAD.as_bytes().to_vec())
+        );
+    }
+}
@@ -0,0 +1,1601 @@
+//! Configurable framing, for forks of OpenUxAS and OpenAMASE that use
+//! different sentinel bytes than the upstream ones.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::iter;
+use core::ops::Range;
+use core::str;
+use memchr::memmem;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use super::checksum::RunningChecksum;
+use super::{
+    CapturedFrame, Checksum, ChecksumCoverage, Error, FrameLayout, Frames, LmcpSentinelizer,
+    ParsedFrame, Result, SentinelEncoder, SentinelKind,
+};
+
+/// How far around the end of the declared payload to look for a misplaced
+/// checksum sentinel before reporting `Error::MisplacedChecksumSentinel`
+const LENGTH_MISMATCH_WINDOW: usize = 16;
+
+/// `Error::MalformedFrame` reasons
+const MISSING_LENGTH: &str = "sentinel directly after the header sentinel, no length field";
+const MISSING_CHECKSUM: &str = "sentinel directly after the checksum sentinel, no checksum field";
+
+/// Size of the pieces `decode_to` checksums and writes the payload in
+#[cfg(feature = "std")]
+const DECODE_CHUNK_LEN: usize = 64 * 1024;
+
+/// How many payload bytes `debug_frame` shows
+const DEBUG_PREVIEW_LEN: usize = 64;
+
+/// Where a frame located at the beginning of the data is, the result of the
+/// validation that all parse functions share
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FrameRanges {
+    pub(crate) payload: Range<usize>,
+    /// Total length of the frame, sentinels included
+    pub(crate) frame_len: usize,
+}
+
+/// Builder for the sentinel byte sequences and limits used by a `Sentinelizer`.
+/// The default reproduces the sentinels of `UxAS_SentinelSerialBuffer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelConfig {
+    before_payload_size: Cow<'static, [u8]>,
+    after_payload_size: Cow<'static, [u8]>,
+    before_checksum: Cow<'static, [u8]>,
+    after_checksum: Cow<'static, [u8]>,
+    max_payload_len: usize,
+    checksum: Checksum,
+    checksum_coverage: ChecksumCoverage,
+    verify_checksum: bool,
+    radix: u32,
+    trim_numeric_spaces: bool,
+    trim_numeric_line_breaks: bool,
+    numeric_width: usize,
+}
+
+impl Default for SentinelConfig {
+    fn default() -> Self {
+        SentinelConfig {
+            before_payload_size: Cow::Borrowed(&LmcpSentinelizer::BEFORE_PAYLOAD_SIZE),
+            after_payload_size: Cow::Borrowed(&LmcpSentinelizer::AFTER_PAYLOAD_SIZE),
+            before_checksum: Cow::Borrowed(&LmcpSentinelizer::BEFORE_CHECKSUM),
+            after_checksum: Cow::Borrowed(&LmcpSentinelizer::AFTER_CHECKSUM),
+            max_payload_len: LmcpSentinelizer::DEFAULT_MAX_PAYLOAD_LEN,
+            checksum: Checksum::Sum,
+            checksum_coverage: ChecksumCoverage::Payload,
+            verify_checksum: true,
+            radix: 10,
+            trim_numeric_spaces: false,
+            trim_numeric_line_breaks: false,
+            numeric_width: 0,
+        }
+    }
+}
+
+impl SentinelConfig {
+    /// Start from the default sentinels
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sentinel preceding the payload length field
+    pub fn before_payload_size(mut self, sentinel: &[u8]) -> Self {
+        self.before_payload_size = Cow::Owned(sentinel.to_vec());
+        self
+    }
+
+    /// Sentinel between the payload length field and the payload
+    pub fn after_payload_size(mut self, sentinel: &[u8]) -> Self {
+        self.after_payload_size = Cow::Owned(sentinel.to_vec());
+        self
+    }
+
+    /// Sentinel between the payload and the checksum field
+    pub fn before_checksum(mut self, sentinel: &[u8]) -> Self {
+        self.before_checksum = Cow::Owned(sentinel.to_vec());
+        self
+    }
+
+    /// Sentinel terminating the frame
+    pub fn after_checksum(mut self, sentinel: &[u8]) -> Self {
+        self.after_checksum = Cow::Owned(sentinel.to_vec());
+        self
+    }
+
+    /// Largest declared payload length accepted when parsing, larger frames
+    /// are rejected with `Error::PayloadTooLarge` before anything is allocated
+    pub fn max_payload_len(mut self, len: usize) -> Self {
+        self.max_payload_len = len;
+        self
+    }
+
+    /// Checksum algorithm of the trailer, the byte sum by default
+    pub fn checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Bytes the checksum is calculated over, only the payload by default to match
+    /// OpenUxAS. Both ends of a link have to agree on it.
+    pub fn checksum_coverage(mut self, coverage: ChecksumCoverage) -> Self {
+        self.checksum_coverage = coverage;
+        self
+    }
+
+    /// Whether parsing checks the payload against the checksum field, on by default.
+    /// Turning it off saves calculating the checksum for links that are known to be
+    /// reliable, e.g. a local loopback; the checksum field still has to be present.
+    pub fn verify_checksum(mut self, verify: bool) -> Self {
+        self.verify_checksum = verify;
+        self
+    }
+
+    /// Radix of the length and checksum fields: 10 as in OpenUxAS, or 16 for forks
+    /// that write them in hex. Hex digits are written in lower case, both cases
+    /// are accepted when parsing. `build` rejects other radixes.
+    pub fn radix(mut self, radix: u32) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    /// Whether parsing skips ASCII spaces before and after the digits of the length
+    /// and checksum fields, off by default. OpenAMASE has been seen to pad the fields,
+    /// e.g. `"  25  "`. Other whitespace is still rejected as
+    /// `Error::WhitespaceInNumericField`, and frames are always written unpadded.
+    pub fn trim_numeric_spaces(mut self, trim: bool) -> Self {
+        self.trim_numeric_spaces = trim;
+        self
+    }
+
+    /// Text tolerant parsing: whether `\r` and `\n` bytes before and after the digits
+    /// of the length and checksum fields are skipped, off by default. Captures from
+    /// some Windows builds of OpenAMASE that went through logs contain `\r\n` around
+    /// the fields. Frames are always written without them.
+    pub fn trim_numeric_line_breaks(mut self, trim: bool) -> Self {
+        self.trim_numeric_line_breaks = trim;
+        self
+    }
+
+    /// Fixed width of the length and checksum fields: values with fewer digits are
+    /// written zero padded to `width` digits, e.g. `0000000025`, and parsing accepts
+    /// leading zeros. Values with more digits are written in full. The default 0
+    /// writes the fields without padding and rejects leading zeros. `build` rejects
+    /// widths above 20, the most digits a field is read with.
+    pub fn numeric_width(mut self, width: usize) -> Self {
+        self.numeric_width = width;
+        self
+    }
+
+    /// Create a sentinelizer using this configuration, after checking that frames
+    /// using it can be parsed unambiguously: the sentinels have to be non-empty and
+    /// distinct, and can't consist of digits only, which would run into the
+    /// numeric fields.
+    pub fn build(self) -> Result<Sentinelizer, ConfigError> {
+        if self.radix != 10 && self.radix != 16 {
+            return Err(ConfigError::UnsupportedRadix { radix: self.radix });
+        }
+        if self.numeric_width > LmcpSentinelizer::MAX_NUMERIC_DIGITS {
+            return Err(ConfigError::UnsupportedNumericWidth {
+                width: self.numeric_width,
+            });
+        }
+        let sentinelizer = Sentinelizer { config: self };
+        for (idx, &which) in SentinelKind::ALL.iter().enumerate() {
+            let sentinel = sentinelizer.sentinel(which);
+            if sentinel.is_empty() {
+                return Err(ConfigError::EmptySentinel { which });
+            }
+            let radix = sentinelizer.config.radix;
+            if sentinel.iter().all(|&c| char::from(c).is_digit(radix)) {
+                return Err(ConfigError::NumericSentinel { which });
+            }
+            if let Some(&first) = SentinelKind::ALL[..idx]
+                .iter()
+                .find(|&&other| sentinelizer.sentinel(other) == sentinel)
+            {
+                return Err(ConfigError::DuplicateSentinel {
+                    first,
+                    second: which,
+                });
+            }
+        }
+        Ok(sentinelizer)
+    }
+}
+
+/// Why `SentinelConfig::build` rejected a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A sentinel without any bytes
+    EmptySentinel { which: SentinelKind },
+    /// Two sentinels with the same bytes
+    DuplicateSentinel {
+        first: SentinelKind,
+        second: SentinelKind,
+    },
+    /// A sentinel made of digits in the configured radix only
+    NumericSentinel { which: SentinelKind },
+    /// A radix other than 10 or 16
+    UnsupportedRadix { radix: u32 },
+    /// A fixed numeric field width above 20 digits
+    UnsupportedNumericWidth { width: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::EmptySentinel { which } => write!(f, "{} sentinel is empty", which),
+            ConfigError::DuplicateSentinel { first, second } => {
+                write!(f, "{} and {} sentinels are identical", first, second)
+            }
+            ConfigError::NumericSentinel { which } => {
+                write!(f, "{} sentinel consists of digits only", which)
+            }
+            ConfigError::UnsupportedRadix { radix } => write!(f, "unsupported radix {}", radix),
+            ConfigError::UnsupportedNumericWidth { width } => {
+                write!(f, "unsupported numeric field width {}", width)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
+/// Frames and parses payloads with the sentinels of its `SentinelConfig`.
+/// The default reproduces `UxAS_SentinelSerialBuffer`, it is what the associated
+/// functions of `LmcpSentinelizer` use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sentinelizer {
+    config: SentinelConfig,
+}
+
+impl Sentinelizer {
+    /// Create a sentinelizer with the default configuration,
+    /// use `SentinelConfig` to change it
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sentinel preceding the payload length field
+    pub fn before_payload_size_sentinel(&self) -> &[u8] {
+        &self.config.before_payload_size
+    }
+
+    /// Sentinel between the payload length field and the payload
+    pub fn after_payload_size_sentinel(&self) -> &[u8] {
+        &self.config.after_payload_size
+    }
+
+    /// Sentinel between the payload and the checksum field
+    pub fn before_checksum_sentinel(&self) -> &[u8] {
+        &self.config.before_checksum
+    }
+
+    /// Sentinel terminating the frame
+    pub fn after_checksum_sentinel(&self) -> &[u8] {
+        &self.config.after_checksum
+    }
+
+    /// Length of the shortest possible frame with the configured sentinels,
+    /// see `LmcpSentinelizer::MIN_FRAME_LEN`
+    pub fn min_frame_len(&self) -> usize {
+        let config = &self.config;
+        config.before_payload_size.len()
+            + config.after_payload_size.len()
+            + config.before_checksum.len()
+            + config.after_checksum.len()
+            + 2
+    }
+
+    /// Checksum of the trailer for the payload, with the configured algorithm
+    /// and coverage
+    pub fn checksum(&self, data: &[u8]) -> u32 {
+        match self.config.checksum_coverage {
+            ChecksumCoverage::Payload => self.config.checksum.compute(data),
+            ChecksumCoverage::LengthAndPayload => {
+                let len_field = self.numeric_field(data.len() as u64);
+                self.frame_checksum(len_field.as_bytes(), data)
+            }
+        }
+    }
+
+    /// Checksum of the trailer for a payload whose length field has the given digits
+    fn frame_checksum(&self, len_field: &[u8], payload: &[u8]) -> u32 {
+        let mut checksum = self.running_checksum(len_field);
+        checksum.update(payload);
+        checksum.value()
+    }
+
+    /// Checksum state to feed the payload of a frame to, which already covers
+    /// the length field if the configured coverage includes it
+    pub(crate) fn running_checksum(&self, len_field: &[u8]) -> RunningChecksum {
+        let mut checksum = RunningChecksum::new(self.config.checksum);
+        if self.config.checksum_coverage == ChecksumCoverage::LengthAndPayload {
+            checksum.update(len_field);
+        }
+        checksum
+    }
+
+    /// Whether the checksum covers nothing but the payload
+    pub(crate) fn checksums_payload_only(&self) -> bool {
+        self.config.checksum_coverage == ChecksumCoverage::Payload
+    }
+
+    /// The digits of the length field of a frame whose payload starts at `payload_start`
+    pub(crate) fn len_field<'a>(&self, data: &'a [u8], payload_start: usize) -> &'a [u8] {
+        let config = &self.config;
+        &data[config.before_payload_size.len()..payload_start - config.after_payload_size.len()]
+    }
+
+    /// The checksum field exactly as it is embedded in the frame for the payload,
+    /// in the configured radix
+    pub fn checksum_string(&self, data: &[u8]) -> String {
+        self.numeric_field(u64::from(self.checksum(data)))
+    }
+
+    /// Check the payload against an expected checksum with the configured algorithm
+    #[must_use = "the checksum is only verified if the result is checked"]
+    pub fn verify(&self, data: &[u8], expected: u32) -> Result<()> {
+        if self.checksum(data) == expected {
+            Ok(())
+        } else {
+            Err(Error::ChecksumVerifyError)
+        }
+    }
+
+    /// Add sentinel strings to the payload, in a buffer of exactly the frame's size
+    pub fn create_sentinelized_stream(&self, data: &[u8]) -> Vec<u8> {
+        let checksum = self.checksum(data);
+        let mut msg = Vec::with_capacity(self.frame_len(data.len(), checksum));
+        self.write_sentinelized_with_checksum(data, checksum, &mut msg);
+        msg
+    }
+
+    /// Add sentinel strings to the payload, return the frame as a boxed slice
+    pub fn create_sentinelized_boxed(&self, data: &[u8]) -> Box<[u8]> {
+        // exactly sized, converting it doesn't reallocate
+        self.create_sentinelized_stream(data).into_boxed_slice()
+    }
+
+    /// Sentinelize each payload and concatenate the frames into one buffer, in order
+    pub fn create_sentinelized_batch(&self, payloads: &[&[u8]]) -> Vec<u8> {
+        let len = payloads.iter().map(|data| self.encoded_len(data)).sum();
+        let mut msg = Vec::with_capacity(len);
+        for data in payloads {
+            self.write_sentinelized(data, &mut msg);
+        }
+        msg
+    }
+
+    /// Append the sentinelized payload to `out`, so one buffer can be reused across frames
+    pub fn write_sentinelized(&self, data: &[u8], out: &mut Vec<u8>) {
+        self.write_sentinelized_with_checksum(data, self.checksum(data), out)
+    }
+
+    /// Write the sentinelized payload to `w` piece by piece, without building the frame
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, data: &[u8], w: &mut W) -> io::Result<()> {
+        self.write_to_with_checksum(data, self.checksum(data), w)
+    }
+
+    /// Process one frame at the beginning of the data and write its payload to `sink`
+    /// instead of collecting it, checksumming it on the way. Return the number of
+    /// bytes the whole frame occupies. The sentinels and fields are all checked
+    /// before anything is written, the checksum only afterwards: on
+    /// `Error::ChecksumVerifyError` the sink has received the corrupt payload.
+    /// Framing errors are converted to `io::Error`s as by `From<Error>`.
+    #[cfg(feature = "std")]
+    pub fn decode_to<W: Write>(&self, data: &[u8], sink: &mut W) -> io::Result<usize> {
+        let FrameRanges { payload, frame_len } = self.locate(data, false, false)?;
+        let mut checksum = self.running_checksum(self.len_field(data, payload.start));
+        for chunk in data[payload.clone()].chunks(DECODE_CHUNK_LEN) {
+            checksum.update(chunk);
+            sink.write_all(chunk)?;
+        }
+        if self.config.verify_checksum
+            && checksum.value() != self.declared_checksum(data, payload.end)
+        {
+            return Err(Error::ChecksumVerifyError.into());
+        }
+        Ok(frame_len)
+    }
+
+    /// Start a frame whose payload is passed in chunks
+    pub fn encoder(&self) -> SentinelEncoder {
+        SentinelEncoder::with_sentinelizer(self.clone())
+    }
+
+    /// The checksum algorithm of the trailer
+    pub(crate) fn checksum_algorithm(&self) -> Checksum {
+        self.config.checksum
+    }
+
+    /// Whether parsed payloads are checked against the checksum field
+    pub(crate) fn verifies_checksum(&self) -> bool {
+        self.config.verify_checksum
+    }
+
+    /// Append the frame for a payload whose checksum is already known
+    pub(crate) fn write_sentinelized_with_checksum(
+        &self,
+        data: &[u8],
+        checksum: u32,
+        out: &mut Vec<u8>,
+    ) {
+        self.write_header(data.len(), out);
+        out.extend_from_slice(data);
+        self.write_trailer(checksum, out);
+    }
+
+    /// The part of the frame before a payload of `payload_len` bytes: the header
+    /// sentinel, the length and the sentinel after it. Together with the payload and
+    /// `encode_trailer` this gives the frame without copying the payload.
+    pub fn encode_header(&self, payload_len: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_header(payload_len, &mut out);
+        out
+    }
+
+    /// The part of the frame after a payload with the given checksum, which the
+    /// caller calculates, e.g. with `checksum`
+    pub fn encode_trailer(&self, checksum: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_trailer(checksum, &mut out);
+        out
+    }
+
+    fn write_header(&self, payload_len: usize, out: &mut Vec<u8>) {
+        let config = &self.config;
+        out.extend_from_slice(&config.before_payload_size);
+        out.extend_from_slice(self.numeric_field(payload_len as u64).as_bytes());
+        out.extend_from_slice(&config.after_payload_size);
+    }
+
+    pub(crate) fn write_trailer(&self, checksum: u32, out: &mut Vec<u8>) {
+        let config = &self.config;
+        out.extend_from_slice(&config.before_checksum);
+        out.extend_from_slice(self.numeric_field(u64::from(checksum)).as_bytes());
+        out.extend_from_slice(&config.after_checksum);
+    }
+
+    /// Write the frame for a payload whose checksum is already known to `w`
+    #[cfg(feature = "std")]
+    pub(crate) fn write_to_with_checksum<W: Write>(
+        &self,
+        data: &[u8],
+        checksum: u32,
+        w: &mut W,
+    ) -> io::Result<()> {
+        let config = &self.config;
+        w.write_all(&config.before_payload_size)?;
+        w.write_all(self.numeric_field(data.len() as u64).as_bytes())?;
+        w.write_all(&config.after_payload_size)?;
+        w.write_all(data)?;
+        w.write_all(&config.before_checksum)?;
+        w.write_all(self.numeric_field(u64::from(checksum)).as_bytes())?;
+        w.write_all(&config.after_checksum)
+    }
+
+    /// Text of a numeric field in the configured radix and width
+    pub(crate) fn numeric_field(&self, val: u64) -> String {
+        let width = self.config.numeric_width;
+        match self.config.radix {
+            16 => format!("{:0width$x}", val, width = width),
+            _ => format!("{:0width$}", val, width = width),
+        }
+    }
+
+    /// Exact number of bytes the payload occupies once sentinelized
+    pub fn encoded_len(&self, data: &[u8]) -> usize {
+        self.frame_len(data.len(), self.checksum(data))
+    }
+
+    /// Length of the frame for a payload of `len` bytes with the given checksum
+    pub(crate) fn frame_len(&self, len: usize, checksum: u32) -> usize {
+        let config = &self.config;
+        config.before_payload_size.len()
+            + digits(len as u64, config.radix).max(config.numeric_width)
+            + config.after_payload_size.len()
+            + len
+            + config.before_checksum.len()
+            + digits(u64::from(checksum), config.radix).max(config.numeric_width)
+            + config.after_checksum.len()
+    }
+
+    /// Process sentinelized data and return a tuple (payload, rem)
+    /// with payload data and remaining data
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_sentinelized_stream(&self, mut data: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>)> {
+        let FrameRanges { payload, frame_len } = self.locate_frame(&data)?;
+        let payload = data[payload].to_vec();
+        data.drain(..frame_len);
+        Ok((payload, data))
+    }
+
+    /// Pop the first frame off the buffer, return a tuple (payload, remaining),
+    /// see `LmcpSentinelizer::pop_frame`
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn pop_frame(&self, data: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.parse_sentinelized_stream(data)
+    }
+
+    /// Borrowing counterpart of `parse_sentinelized_stream`, return a tuple
+    /// (payload, rem) of slices into the original buffer
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_sentinelized_slice<'a>(&self, data: &'a [u8]) -> Result<(&'a [u8], &'a [u8])> {
+        let FrameRanges { payload, frame_len } = self.locate_frame(data)?;
+        Ok((&data[payload], &data[frame_len..]))
+    }
+
+    /// Process sentinelized data without copying it, return the payload
+    /// as a slice into the original buffer
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_sentinelized_stream_ref<'a>(&self, data: &'a [u8]) -> Result<&'a [u8]> {
+        let FrameRanges { payload, .. } = self.locate_frame(data)?;
+        Ok(&data[payload])
+    }
+
+    /// Validate the frame at the beginning of the buffer and drain its header,
+    /// see `LmcpSentinelizer::parse_in_place`
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_in_place(&self, data: &mut Vec<u8>) -> Result<(usize, usize)> {
+        let FrameRanges { payload, .. } = self.locate_frame(data)?;
+        data.drain(..payload.start);
+        Ok((0, payload.len()))
+    }
+
+    /// Sentinelize the payload and parse the frame again, return the recovered payload
+    pub fn round_trip(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let frame = self.create_sentinelized_stream(data);
+        let (payload, _) = self.parse_one(&frame)?;
+        Ok(payload)
+    }
+
+    /// Verify the frame at the beginning of the data, return the byte ranges of its sections
+    pub fn frame_layout(&self, data: &[u8]) -> Result<FrameLayout> {
+        let config = &self.config;
+        let FrameRanges { payload, frame_len } = self.locate_frame(data).map_err(at_eof)?;
+        let len_end = payload.start - config.after_payload_size.len();
+        let checksum_start = payload.end + config.before_checksum.len();
+        let checksum_end = frame_len - config.after_checksum.len();
+        Ok(FrameLayout {
+            before_payload_size: 0..config.before_payload_size.len(),
+            payload_len: config.before_payload_size.len()..len_end,
+            after_payload_size: len_end..payload.start,
+            before_checksum: payload.end..checksum_start,
+            checksum: checksum_start..checksum_end,
+            after_checksum: checksum_end..frame_len,
+            payload,
+        })
+    }
+
+    /// Render the frame at the beginning of the data for logs, section by section, see
+    /// `LmcpSentinelizer::debug_frame`
+    pub fn debug_frame(&self, data: &[u8]) -> String {
+        let layout = match self.frame_layout(data) {
+            Ok(layout) => layout,
+            Err(e) => return format!("<invalid frame: {}> {}", e, preview(data)),
+        };
+        let text = |range: Range<usize>| String::from_utf8_lossy(&data[range]);
+        format!(
+            "{} | len {} | {} | {} ({} bytes) | {} | checksum {} | {}",
+            text(layout.before_payload_size),
+            text(layout.payload_len),
+            text(layout.after_payload_size),
+            preview(&data[layout.payload.clone()]),
+            layout.payload.len(),
+            text(layout.before_checksum),
+            text(layout.checksum),
+            text(layout.after_checksum),
+        )
+    }
+
+    /// Parse the frame at the beginning of the data, pass its payload to `f` and
+    /// sentinelize the result, with the length and checksum of the new payload
+    pub fn reframe(&self, old_frame: &[u8], f: impl FnOnce(&[u8]) -> Vec<u8>) -> Result<Vec<u8>> {
+        let FrameRanges { payload, .. } = self.locate_frame(old_frame).map_err(at_eof)?;
+        Ok(self.create_sentinelized_stream(&f(&old_frame[payload])))
+    }
+
+    /// Process one frame at the beginning of the data, return a tuple (payload, consumed)
+    /// with the payload data and the number of bytes the whole frame occupies
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_one(&self, data: &[u8]) -> Result<(Vec<u8>, usize)> {
+        let FrameRanges { payload, frame_len } = self.locate_frame(data)?;
+        Ok((data[payload].to_vec(), frame_len))
+    }
+
+    /// Check the sentinels and the checksum of the frame at the beginning of the data
+    /// without copying the payload out, return the number of bytes the frame occupies
+    #[must_use = "the frame is only validated if the result is checked"]
+    pub fn validate(&self, data: &[u8]) -> Result<usize> {
+        let FrameRanges { frame_len, .. } = self.locate_frame(data).map_err(at_eof)?;
+        Ok(frame_len)
+    }
+
+    /// Process data that has to consist of exactly one frame, return its payload
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_strict(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let FrameRanges { payload, frame_len } = self.locate_frame(data).map_err(at_eof)?;
+        if frame_len != data.len() {
+            trace_event!(
+                debug,
+                frame_len = frame_len,
+                trailing = data.len() - frame_len,
+                "trailing bytes after the frame"
+            );
+            return Err(Error::TrailingBytes);
+        }
+        Ok(data[payload].to_vec())
+    }
+
+    /// Process one frame at the beginning of the data, return the payload
+    /// together with the declared length, checksum and frame length
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_frame(&self, data: &[u8]) -> Result<ParsedFrame> {
+        let FrameRanges { payload, frame_len } = self.locate_frame(data).map_err(at_eof)?;
+        Ok(ParsedFrame {
+            declared_len: payload.len(),
+            checksum: self.declared_checksum(data, payload.end),
+            payload: data[payload].to_vec(),
+            frame_len,
+        })
+    }
+
+    /// Process one frame at the beginning of the data whose payload length is known
+    /// out of band, return its payload. A different declared length is reported as
+    /// `Error::LengthMismatch` as soon as the header is complete.
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_with_expected_len(&self, data: &[u8], expected: usize) -> Result<Vec<u8>> {
+        let declared = self.peek_declared_len(data).map_err(at_eof)?;
+        if declared != expected {
+            return Err(Error::LengthMismatch { declared, expected });
+        }
+        let (payload, _) = self.parse_one(data).map_err(at_eof)?;
+        Ok(payload)
+    }
+
+    /// Process a frame held as text, e.g. a line of a log, like `parse_strict` does
+    /// with its UTF-8 bytes
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_str(&self, s: &str) -> Result<Vec<u8>> {
+        self.parse_strict(s.as_bytes())
+    }
+
+    /// Process all frames in the data, return the payload of each frame in order,
+    /// with the same stopping rules as `frames`
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_all(&self, data: &[u8]) -> Vec<Result<Vec<u8>>> {
+        self.frames(data)
+            .map(|payload| payload.map(<[u8]>::to_vec))
+            .collect()
+    }
+
+    /// Process all frames in the data like `parse_all`, but after an error, look for
+    /// the next frame header as `parse_resync` does and continue there instead of
+    /// stopping. Every failed frame, or run of garbage, is one error in the result.
+    /// An incomplete frame only ends the data when no frame header follows it; one
+    /// that is followed by another header had a corrupt length field.
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_all_resync(&self, data: &[u8]) -> Vec<Result<Vec<u8>>> {
+        let mut res = Vec::new();
+        let mut rem = data;
+        while !rem.is_empty() {
+            match self.parse_one(rem) {
+                Ok((payload, consumed)) => {
+                    res.push(Ok(payload));
+                    rem = &rem[consumed..];
+                }
+                Err(e) => {
+                    res.push(Err(e));
+                    // the header of the bad frame may be valid, look after its start
+                    match self.find_header(&rem[1..], usize::MAX) {
+                        Ok(start) => rem = &rem[1 + start..],
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Process all frames in the data like `parse_all`, but when the final frame is
+    /// incomplete and its header is complete, report how much of its payload was
+    /// received as `CapturedFrame::Partial` instead of `Error::Incomplete`
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_all_lenient(&self, data: &[u8]) -> Vec<Result<CapturedFrame>> {
+        let mut frames = self.frames(data);
+        let mut res = Vec::new();
+        loop {
+            let rem = frames.remaining();
+            match frames.next() {
+                None => return res,
+                Some(Ok(payload)) => res.push(Ok(CapturedFrame::Complete(payload.to_vec()))),
+                Some(Err(e @ Error::Incomplete { .. })) => {
+                    res.push(match self.locate_header(rem) {
+                        Ok(payload) => Ok(CapturedFrame::Partial {
+                            received: rem.len().min(payload.end) - payload.start,
+                            declared: payload.len(),
+                        }),
+                        Err(_) => Err(e),
+                    });
+                }
+                Some(Err(e)) => res.push(Err(e)),
+            }
+        }
+    }
+
+    /// Iterate lazily over the payloads of all frames in the data
+    pub fn frames<'a>(&self, data: &'a [u8]) -> Frames<'a> {
+        Frames::new(self.clone(), data)
+    }
+
+    /// Iterate lazily over the payloads of all frames in the data like `frames`, each
+    /// with the offset in the data where its frame begins
+    pub fn frames_with_offsets<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (Result<&'a [u8]>, usize)> + 'a {
+        let mut frames = self.frames(data);
+        iter::from_fn(move || {
+            let offset = frames.offset();
+            frames.next().map(|payload| (payload, offset))
+        })
+    }
+
+    /// Read the declared payload length from the frame header at the beginning of the
+    /// data, without looking at the payload or the checksum.
+    /// `Error::Incomplete` means the header hasn't been received completely yet.
+    pub fn peek_declared_len(&self, data: &[u8]) -> Result<usize> {
+        let config = &self.config;
+        let idx = self.expect_sentinel(data, 0, SentinelKind::BeforePayloadSize)?;
+        let (len, _) = self
+            .expect_numeric::<usize>(data, idx)
+            .map_err(|e| self.missing_field(e, data, idx, MISSING_LENGTH))?;
+        if len > config.max_payload_len {
+            return Err(Error::PayloadTooLarge);
+        }
+        Ok(len)
+    }
+
+    /// Whether the data begins with the header sentinel. Data shorter than the
+    /// sentinel is never a match, even when it is a prefix of it.
+    pub fn looks_like_sentinel_frame(&self, data: &[u8]) -> bool {
+        data.starts_with(&self.config.before_payload_size)
+    }
+
+    /// Return the offset of the first header sentinel in the data, if any
+    pub fn find_frame_start(&self, data: &[u8]) -> Option<usize> {
+        memmem::find(data, &self.config.before_payload_size)
+    }
+
+    /// Skip garbage before the first frame header and process the frame there.
+    /// Header sentinels that aren't followed by a length field and the sentinel
+    /// after it, e.g. ones inside the payload of a corrupted frame, are skipped too.
+    /// Return a tuple (payload, discarded) with the payload data and the number
+    /// of leading bytes that were skipped.
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_resync(&self, data: &[u8]) -> Result<(Vec<u8>, usize)> {
+        self.parse_resync_bounded(data, usize::MAX)
+    }
+
+    /// Resync like `parse_resync`, but only look for a frame header in the first
+    /// `max_scan` bytes, to bound the work spent on junk. When the data is longer
+    /// and has no header there, `Error::ResyncLimitExceeded` is returned instead
+    /// of `Error::SentinelNotFound`; the scanned bytes can be dropped before the
+    /// next call.
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_resync_bounded(&self, data: &[u8], max_scan: usize) -> Result<(Vec<u8>, usize)> {
+        let start = self.find_header(data, max_scan)?;
+        let (payload, _) = self.parse_one(&data[start..])?;
+        Ok((payload, start))
+    }
+
+    /// Return the offset of the first header sentinel that starts within the first
+    /// `max_scan` bytes and is followed by a valid length field and the sentinel
+    /// after it, or a prefix of them
+    fn find_header(&self, data: &[u8], max_scan: usize) -> Result<usize> {
+        let sentinel = &self.config.before_payload_size;
+        let scan_end = max_scan
+            .saturating_add(sentinel.len().saturating_sub(1))
+            .min(data.len());
+        memmem::find_iter(&data[..scan_end], sentinel)
+            .find(|&start| match self.locate_header(&data[start..]) {
+                Ok(_) | Err(Error::Incomplete { .. }) => true,
+                Err(_) => false,
+            })
+            .ok_or(if scan_end < data.len() {
+                Error::ResyncLimitExceeded
+            } else {
+                Error::SentinelNotFound {
+                    which: SentinelKind::BeforePayloadSize,
+                }
+            })
+    }
+
+    /// Process one frame at the beginning of the data like `parse_frame`, but when the
+    /// checksum sentinel doesn't follow the declared payload length, look for it close
+    /// by and take the payload up to there, for peers that get the length slightly
+    /// wrong. Return the frame, whose `declared_len` is the length of the header
+    /// field, and whether the payload length had to be corrected. The payload still
+    /// has to match the checksum.
+    #[must_use = "the payload is lost if the result is ignored"]
+    pub fn parse_lenient(&self, data: &[u8]) -> Result<(ParsedFrame, bool)> {
+        let FrameRanges { payload, frame_len } = self
+            .locate(data, true, self.config.verify_checksum)
+            .map_err(at_eof)?;
+        let declared_len = self.peek_declared_len(data)?;
+        let checksum = self.declared_checksum(data, payload.end);
+        let payload = data[payload].to_vec();
+        let recovered = payload.len() != declared_len;
+        Ok((
+            ParsedFrame {
+                declared_len,
+                checksum,
+                payload,
+                frame_len,
+            },
+            recovered,
+        ))
+    }
+
+    /// Split the data into complete frames and the trailing bytes after them, e.g. to
+    /// re-queue the remainder of a socket buffer, without copying anything. Return a
+    /// tuple (frames, rem) with the bytes of each frame, sentinels included, and the
+    /// rest of the data. Checksums aren't checked, that is left to the caller, e.g.
+    /// with `parse_sentinelized_slice` per frame. The remainder starts at the first
+    /// bytes that aren't a complete frame, usually the prefix of the next frame;
+    /// when it is corrupted instead, processing the remainder reports why.
+    #[must_use = "the remainder has to be kept for the next read"]
+    pub fn split_frames<'a>(&self, mut data: &'a [u8]) -> (Vec<&'a [u8]>, &'a [u8]) {
+        let mut frames = Vec::new();
+        while let Ok(FrameRanges { frame_len, .. }) = self.locate(data, false, false) {
+            let (frame, rem) = data.split_at(frame_len);
+            frames.push(frame);
+            data = rem;
+        }
+        (frames, data)
+    }
+
+    /// Locate a complete frame at the beginning of the data without copying it,
+    /// the core of all parse functions, which only differ in what they make of
+    /// the ranges.
+    /// `Error::Incomplete` means the data is a valid but incomplete frame prefix.
+    pub(crate) fn locate_frame(&self, data: &[u8]) -> Result<FrameRanges> {
+        self.locate(data, false, self.config.verify_checksum)
+    }
+
+    /// `locate_frame`, optionally searching for a misplaced checksum sentinel
+    /// and optionally checking the checksum
+    fn locate(&self, data: &[u8], lenient: bool, verify: bool) -> Result<FrameRanges> {
+        let payload = self.locate_header(data)?;
+        if payload.end > data.len() {
+            return Err(incomplete(
+                Error::Incomplete { needed: None },
+                data,
+                self.min_frame_end(payload.end),
+            ));
+        }
+        self.locate_trailer(data, payload, lenient, verify, None)
+    }
+
+    /// Check the frame header at the beginning of the data, return the range the
+    /// declared payload occupies, which may extend beyond the data
+    pub(crate) fn locate_header(&self, data: &[u8]) -> Result<Range<usize>> {
+        let config = &self.config;
+        let idx = self
+            .expect_sentinel(data, 0, SentinelKind::BeforePayloadSize)
+            .map_err(|e| sentinel_failed(e, 0))?;
+        let (len, len_idx) = self
+            .expect_numeric::<usize>(data, idx)
+            .map_err(|e| self.missing_field(e, data, idx, MISSING_LENGTH))
+            .map_err(|e| numeric_failed(e, "payload_len", idx))?;
+        if len > config.max_payload_len {
+            trace_event!(
+                warn,
+                declared_len = len,
+                max_payload_len = config.max_payload_len,
+                "payload too large"
+            );
+            return Err(Error::PayloadTooLarge);
+        }
+        let idx = self
+            .expect_sentinel(data, len_idx, SentinelKind::AfterPayloadSize)
+            .map_err(|e| sentinel_failed(e, len_idx))
+            .map_err(|e| {
+                let payload_end = (len_idx + config.after_payload_size.len()).saturating_add(len);
+                incomplete(e, data, self.min_frame_end(payload_end))
+            })?;
+        Ok(idx..idx.saturating_add(len))
+    }
+
+    /// Check the frame trailer after the payload, whose bytes have to be in the data,
+    /// return the payload range and the length of the frame. The declared checksum is
+    /// only checked if `verify` is set, `checksum` is the checksum of the payload if
+    /// the caller already calculated it.
+    pub(crate) fn locate_trailer(
+        &self,
+        data: &[u8],
+        mut payload: Range<usize>,
+        lenient: bool,
+        verify: bool,
+        checksum: Option<u32>,
+    ) -> Result<FrameRanges> {
+        let config = &self.config;
+        let len = payload.len();
+        let trailer_min_len = self
+            .min_frame_end(payload.end)
+            .max(data.len() + config.after_checksum.len());
+        let mut res = self.expect_sentinel(data, payload.end, SentinelKind::BeforeChecksum);
+        if lenient && matches!(res, Err(Error::SentinelNotFound { .. })) {
+            if let Some(end) = self.nearby_payload_end(data, &payload) {
+                payload.end = end;
+                trace_event!(
+                    debug,
+                    declared_len = len,
+                    recovered_len = payload.len(),
+                    "recovered the payload length from the checksum sentinel"
+                );
+                res = Ok(end + config.before_checksum.len());
+            }
+        }
+        if matches!(res, Err(Error::SentinelNotFound { .. })) {
+            if let Some(end) = self.nearby_payload_end(data, &payload) {
+                trace_event!(
+                    warn,
+                    declared_len = len,
+                    offset = payload.end,
+                    "checksum sentinel is off, the declared length is wrong"
+                );
+                return Err(Error::MisplacedChecksumSentinel {
+                    declared: len,
+                    actual: end - payload.start,
+                });
+            }
+        }
+        let idx = res
+            .map_err(|e| sentinel_failed(e, payload.end))
+            .map_err(|e| incomplete(e, data, trailer_min_len))?;
+        let (declared, checksum_idx) = self
+            .expect_numeric::<u32>(data, idx)
+            .map_err(|e| self.missing_field(e, data, idx, MISSING_CHECKSUM))
+            .map_err(|e| numeric_failed(e, "checksum", idx))
+            .map_err(|e| incomplete(e, data, trailer_min_len))?;
+        let idx = self
+            .expect_sentinel(data, checksum_idx, SentinelKind::AfterChecksum)
+            .map_err(|e| sentinel_failed(e, checksum_idx))
+            .map_err(|e| incomplete(e, data, checksum_idx + config.after_checksum.len()))?;
+        if !verify {
+            return Ok(FrameRanges {
+                payload,
+                frame_len: idx,
+            });
+        }
+        let computed = match checksum {
+            Some(checksum) if payload.len() == len => checksum,
+            _ => self.frame_checksum(self.len_field(data, payload.start), &data[payload.clone()]),
+        };
+        if computed != declared {
+            trace_event!(
+                warn,
+                declared = declared,
+                computed = computed,
+                frame_len = idx,
+                "checksum mismatch"
+            );
+            return Err(Error::ChecksumVerifyError);
+        }
+        Ok(FrameRanges {
+            payload,
+            frame_len: idx,
+        })
+    }
+
+    /// Value of the checksum field of a located frame whose payload ends at `payload_end`
+    fn declared_checksum(&self, data: &[u8], payload_end: usize) -> u32 {
+        let idx = payload_end + self.config.before_checksum.len();
+        self.expect_numeric(data, idx)
+            .map_or(0, |(checksum, _)| checksum)
+    }
+
+    /// Shortest frame with a payload ending at `payload_end`,
+    /// for reporting how much of an incomplete frame is missing
+    fn min_frame_end(&self, payload_end: usize) -> usize {
+        let config = &self.config;
+        payload_end.saturating_add(config.before_checksum.len() + 1 + config.after_checksum.len())
+    }
+
+    /// Return the offset of the first byte after the start of the data where a frame
+    /// header could begin, i.e. where the header sentinel or a prefix of it starts
+    pub(crate) fn next_frame_candidate(&self, data: &[u8]) -> usize {
+        let sentinel = &self.config.before_payload_size;
+        let first = match sentinel.first() {
+            Some(&first) => first,
+            None => return data.len().min(1),
+        };
+        let mut idx = 1;
+        while let Some(pos) = data.get(idx..).and_then(|rem| memchr::memchr(first, rem)) {
+            idx += pos;
+            let rem = &data[idx..];
+            let len = rem.len().min(sentinel.len());
+            if rem[..len] == sentinel[..len] {
+                return idx;
+            }
+            idx += 1;
+        }
+        data.len()
+    }
+
+    /// Find the checksum sentinel closest to the declared end of the payload,
+    /// return the position where it starts
+    fn nearby_payload_end(&self, data: &[u8], payload: &Range<usize>) -> Option<usize> {
+        let sentinel = &self.config.before_checksum;
+        let is_sentinel = |end: usize| data.get(end..).is_some_and(|rem| rem.starts_with(sentinel));
+        (1..=LENGTH_MISMATCH_WINDOW).find_map(|dist| {
+            if payload.end - payload.start >= dist && is_sentinel(payload.end - dist) {
+                Some(payload.end - dist)
+            } else if is_sentinel(payload.end + dist) {
+                Some(payload.end + dist)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Recognize a numeric field that is missing altogether, with another sentinel
+    /// right after the one that precedes the field, which happens when the stream
+    /// lost bytes rather than when a peer wrote a bad value
+    fn missing_field(&self, err: Error, data: &[u8], idx: usize, reason: &'static str) -> Error {
+        let rem = data.get(idx..).unwrap_or(&[]);
+        let sentinel_follows = SentinelKind::ALL.iter().any(|&which| {
+            let sentinel = self.sentinel(which);
+            !sentinel.is_empty()
+                && !rem.is_empty()
+                && (rem.starts_with(sentinel) || sentinel.starts_with(rem))
+        });
+        if err == Error::InvalidNumericField && sentinel_follows {
+            trace_event!(debug, offset = idx, reason = reason, "malformed frame");
+            Error::MalformedFrame { reason }
+        } else {
+            err
+        }
+    }
+
+    /// Parse the numeric field at `idx` with the configured radix and padding
+    fn expect_numeric<T: TryFrom<u64>>(&self, data: &[u8], idx: usize) -> Result<(T, usize)> {
+        let config = &self.config;
+        let padding: &[u8] = match (config.trim_numeric_spaces, config.trim_numeric_line_breaks) {
+            (false, false) => b"",
+            (true, false) => b" ",
+            (false, true) => b"\r\n",
+            (true, true) => b" \r\n",
+        };
+        let leading_zeros = config.numeric_width > 0;
+        LmcpSentinelizer::expect_numeric(data, idx, config.radix, padding, leading_zeros)
+    }
+
+    /// Check that the sentinel starts at `idx`, return the index right after it
+    fn expect_sentinel(&self, data: &[u8], idx: usize, which: SentinelKind) -> Result<usize> {
+        let sentinel = self.sentinel(which);
+        let rem = data.get(idx..).unwrap_or(&[]);
+        if rem.starts_with(sentinel) {
+            Ok(idx + sentinel.len())
+        } else if sentinel.starts_with(rem) {
+            Err(Error::Incomplete { needed: None })
+        } else {
+            Err(Error::SentinelNotFound { which })
+        }
+    }
+
+    /// The configured bytes of a sentinel
+    fn sentinel(&self, which: SentinelKind) -> &[u8] {
+        match which {
+            SentinelKind::BeforePayloadSize => &self.config.before_payload_size,
+            SentinelKind::AfterPayloadSize => &self.config.after_payload_size,
+            SentinelKind::BeforeChecksum => &self.config.before_checksum,
+            SentinelKind::AfterChecksum => &self.config.after_checksum,
+        }
+    }
+}
+
+/// Show the first `DEBUG_PREVIEW_LEN` bytes, quoted if they are UTF-8 and as hex otherwise
+fn preview(data: &[u8]) -> String {
+    let shown = &data[..data.len().min(DEBUG_PREVIEW_LEN)];
+    let more = if shown.len() < data.len() { "..." } else { "" };
+    let text = match str::from_utf8(shown) {
+        Ok(text) => Some(text),
+        // a character cut off at the end of the preview
+        Err(e) if e.error_len().is_none() => str::from_utf8(&shown[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+    match text {
+        Some(text) => format!("{:?}{}", text, more),
+        None => {
+            let hex: String = shown.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("0x{}{}", hex, more)
+        }
+    }
+}
+
+/// Report a sentinel that isn't where it should be. Running out of bytes isn't
+/// reported, it is the normal case for a frame that is still arriving.
+fn sentinel_failed(err: Error, offset: usize) -> Error {
+    if let Error::SentinelNotFound { which } = err {
+        trace_event!(
+            debug,
+            sentinel = which.as_str(),
+            offset = offset,
+            "sentinel not found"
+        );
+    }
+    err
+}
+
+/// Report a length or checksum field that can't be parsed
+fn numeric_failed(err: Error, field: &'static str, offset: usize) -> Error {
+    if !matches!(err, Error::Incomplete { .. }) {
+        trace_event!(
+            debug,
+            field = field,
+            offset = offset,
+            "invalid numeric field"
+        );
+    }
+    err
+}
+
+/// Fill in the number of missing bytes of an incomplete frame of at least `min_len` bytes
+fn incomplete(err: Error, data: &[u8], min_len: usize) -> Error {
+    match err {
+        Error::Incomplete { .. } => Error::Incomplete {
+            needed: Some(min_len.saturating_sub(data.len())),
+        },
+        e => e,
+    }
+}
+
+/// Report an incomplete frame as `Error::UnexpectedEof`, for the functions that
+/// take the data as a whole frame, which can't grow anymore
+fn at_eof(err: Error) -> Error {
+    match err {
+        Error::Incomplete { .. } => Error::UnexpectedEof,
+        e => e,
+    }
+}
+
+/// Number of decimal digits of the value
+fn digits(mut val: u64, radix: u32) -> usize {
+    let radix = u64::from(radix);
+    let mut digits = 1;
+    while val >= radix {
+        val /= radix;
+        digits += 1;
+    }
+    digits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SentinelDecoder;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_default_config() {
+        let sentinelizer = SentinelConfig::new().build().unwrap();
+        assert_eq!(
+            sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes()),
+            TEST_DATA.as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_default_instance_matches_statics() {
+        let sentinelizer = Sentinelizer::new();
+        assert_eq!(sentinelizer, SentinelConfig::new().build().unwrap());
+
+        let payload = TEST_PAYLOAD.as_bytes();
+        assert_eq!(
+            sentinelizer.create_sentinelized_stream(payload),
+            LmcpSentinelizer::create_sentinelized_stream(payload)
+        );
+        assert_eq!(
+            sentinelizer.encoded_len(payload),
+            LmcpSentinelizer::encoded_len(payload)
+        );
+        assert_eq!(
+            sentinelizer.checksum(payload),
+            LmcpSentinelizer::checksum(payload)
+        );
+        assert_eq!(
+            sentinelizer.verify(payload, 1925),
+            LmcpSentinelizer::verify(payload, 1925)
+        );
+
+        let frame = TEST_DATA.as_bytes();
+        let corrupted = TEST_DATA.replace("1925", "1926");
+        let inputs: [&[u8]; 6] = [
+            frame,
+            &frame[..30],
+            corrupted.as_bytes(),
+            b"garbage+=+=+=+=",
+            &[],
+            &[frame, frame].concat(),
+        ];
+        for data in inputs.iter() {
+            assert_eq!(
+                sentinelizer.parse_one(data),
+                LmcpSentinelizer::parse_one(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_frame(data),
+                LmcpSentinelizer::parse_frame(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_strict(data),
+                LmcpSentinelizer::parse_strict(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_all(data),
+                LmcpSentinelizer::parse_all(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_resync(data),
+                LmcpSentinelizer::parse_resync(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_sentinelized_stream_ref(data),
+                LmcpSentinelizer::parse_sentinelized_stream_ref(data)
+            );
+            assert_eq!(
+                sentinelizer.parse_sentinelized_stream(data.to_vec()),
+                LmcpSentinelizer::parse_sentinelized_stream(data.to_vec())
+            );
+            let (mut a, mut b) = (data.to_vec(), data.to_vec());
+            assert_eq!(
+                sentinelizer.parse_in_place(&mut a),
+                LmcpSentinelizer::parse_in_place(&mut b)
+            );
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn test_find_frame_start_offsets() {
+        let sentinelizer = Sentinelizer::new();
+        for &offset in &[0, 1, 7, 8, 100, 4095] {
+            // near misses of the sentinel, padded to the offset
+            let mut data = b"+=+=+=+-".repeat(offset / 8);
+            data.resize(offset, b'x');
+            data.extend_from_slice(TEST_DATA.as_bytes());
+            assert_eq!(sentinelizer.find_frame_start(&data), Some(offset));
+        }
+        assert_eq!(sentinelizer.find_frame_start(b"+=+=+=+"), None);
+
+        // candidates include a sentinel prefix at the very end
+        assert_eq!(sentinelizer.next_frame_candidate(b"+=+=xx+=+"), 6);
+        assert_eq!(sentinelizer.next_frame_candidate(b"+=+=+=+=+="), 2);
+        assert_eq!(sentinelizer.next_frame_candidate(b"xxxx"), 4);
+    }
+
+    #[test]
+    fn test_build_rejects_ambiguous_sentinels() {
+        assert_eq!(
+            SentinelConfig::new().after_checksum(b"+=+=+=+=").build(),
+            Err(ConfigError::DuplicateSentinel {
+                first: SentinelKind::BeforePayloadSize,
+                second: SentinelKind::AfterChecksum
+            })
+        );
+        assert_eq!(
+            SentinelConfig::new().before_checksum(b"1234").build(),
+            Err(ConfigError::NumericSentinel {
+                which: SentinelKind::BeforeChecksum
+            })
+        );
+        assert_eq!(
+            SentinelConfig::new().after_payload_size(b"").build(),
+            Err(ConfigError::EmptySentinel {
+                which: SentinelKind::AfterPayloadSize
+            })
+        );
+        // hex digits only count in hex
+        assert!(SentinelConfig::new()
+            .after_checksum(b"cafe")
+            .build()
+            .is_ok());
+        assert_eq!(
+            SentinelConfig::new()
+                .after_checksum(b"cafe")
+                .radix(16)
+                .build(),
+            Err(ConfigError::NumericSentinel {
+                which: SentinelKind::AfterChecksum
+            })
+        );
+        assert_eq!(
+            SentinelConfig::new().radix(8).build(),
+            Err(ConfigError::UnsupportedRadix { radix: 8 })
+        );
+        assert_eq!(
+            ConfigError::DuplicateSentinel {
+                first: SentinelKind::BeforePayloadSize,
+                second: SentinelKind::AfterChecksum
+            }
+            .to_string(),
+            "before_payload_size and after_checksum sentinels are identical"
+        );
+    }
+
+    #[test]
+    fn test_custom_sentinels_round_trip() {
+        let sentinelizer = SentinelConfig::new()
+            .before_payload_size(b"<<")
+            .after_payload_size(b"|")
+            .before_checksum(b"~~~~~~~~~~")
+            .after_checksum(b">>>>")
+            .build()
+            .unwrap();
+        let frame = sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+        assert_eq!(
+            frame,
+            b"<<25|ABCDEFGHIJKLMNOPQRSTUVWXY~~~~~~~~~~1925>>>>".to_vec()
+        );
+
+        let (payload, rem) = sentinelizer.parse_sentinelized_stream(frame).unwrap();
+        assert_eq!(payload, TEST_PAYLOAD.as_bytes().to_vec());
+        assert!(rem.is_empty());
+
+        // the default sentinels are not accepted anymore
+        assert_eq!(
+            sentinelizer.parse_one(TEST_DATA.as_bytes()),
+            Err(Error::SentinelNotFound {
+                which: SentinelKind::BeforePayloadSize
+            })
+        );
+    }
+
+    #[test]
+    fn test_skip_checksum_verification() {
+        let unverified = SentinelConfig::new()
+            .verify_checksum(false)
+            .build()
+            .unwrap();
+        let corrupted = TEST_DATA.replace("1925", "1");
+        assert_eq!(
+            Sentinelizer::default().parse_one(corrupted.as_bytes()),
+            Err(Error::ChecksumVerifyError)
+        );
+        assert_eq!(
+            unverified.parse_one(corrupted.as_bytes()),
+            Ok((TEST_PAYLOAD.as_bytes().to_vec(), corrupted.len()))
+        );
+        let frame = unverified.parse_frame(corrupted.as_bytes()).unwrap();
+        assert_eq!(frame.checksum, 1);
+
+        // the field is still parsed to find the end of the frame
+        let missing = TEST_DATA.replace("1925", "");
+        assert_eq!(
+            unverified.parse_one(missing.as_bytes()),
+            Err(Error::MalformedFrame {
+                reason: MISSING_CHECKSUM
+            })
+        );
+
+        let mut decoder = SentinelDecoder::with_sentinelizer(unverified);
+        for chunk in corrupted.as_bytes().chunks(7) {
+            decoder.push_bytes(chunk);
+        }
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_checksum_string_radix() {
+        let hex = SentinelConfig::new().radix(16).build().unwrap();
+        assert_eq!(hex.checksum_string(TEST_PAYLOAD.as_bytes()), "785");
+        let crc = SentinelConfig::new()
+            .checksum(Checksum::Crc32)
+            .build()
+            .unwrap();
+        assert_eq!(
+            crc.checksum_string(TEST_PAYLOAD.as_bytes()),
+            Checksum::Crc32.compute(TEST_PAYLOAD.as_bytes()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_trim_numeric_spaces() {
+        let padded = "+=+=+=+=  25  #@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!% 1925 ?^?^?^?^";
+        let strict = Sentinelizer::default();
+        assert_eq!(
+            strict.parse_one(padded.as_bytes()),
+            Err(Error::WhitespaceInNumericField)
+        );
+
+        let tolerant = SentinelConfig::new()
+            .trim_numeric_spaces(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            tolerant.parse_one(padded.as_bytes()),
+            Ok((TEST_PAYLOAD.as_bytes().to_vec(), padded.len()))
+        );
+        assert_eq!(tolerant.peek_declared_len(padded.as_bytes()), Ok(25));
+        let mut decoder = SentinelDecoder::with_sentinelizer(tolerant.clone());
+        decoder.push_bytes(padded.as_bytes());
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        // unpadded frames still parse, and are what gets written
+        assert!(tolerant.parse_one(TEST_DATA.as_bytes()).is_ok());
+        assert_eq!(
+            tolerant.create_sentinelized_stream(TEST_PAYLOAD.as_bytes()),
+            TEST_DATA.as_bytes()
+        );
+
+        // only spaces are trimmed
+        let tab = padded.replace("  25  ", "\t25");
+        assert_eq!(
+            tolerant.parse_one(tab.as_bytes()),
+            Err(Error::WhitespaceInNumericField)
+        );
+        let cr = padded.replace("  25  ", "25\r");
+        assert_eq!(
+            tolerant.parse_one(cr.as_bytes()),
+            Err(Error::WhitespaceInNumericField)
+        );
+        // a field of nothing but spaces
+        let blank = padded.replace("  25  ", "   ");
+        assert_eq!(
+            tolerant.parse_one(blank.as_bytes()),
+            Err(Error::InvalidNumericField)
+        );
+        // cut off inside the padded field
+        assert!(matches!(
+            tolerant.parse_one(&padded.as_bytes()[..11]),
+            Err(Error::Incomplete { .. })
+        ));
+    }
+
+    #[test]
+    fn test_trim_numeric_line_breaks() {
+        let after_len = TEST_DATA.replace("25#@", "25\r\n#@");
+        let after_checksum = TEST_DATA.replace("1925", "1925\r\n");
+        let both = after_len.replace("1925", "\r\n1925\r\n");
+        let strict = Sentinelizer::default();
+        let tolerant = SentinelConfig::new().trim_numeric_line_breaks(true);
+        for data in &[&after_len, &after_checksum, &both] {
+            assert_eq!(
+                strict.parse_one(data.as_bytes()),
+                Err(Error::WhitespaceInNumericField)
+            );
+            assert_eq!(
+                tolerant.clone().build().unwrap().parse_one(data.as_bytes()),
+                Ok((TEST_PAYLOAD.as_bytes().to_vec(), data.len()))
+            );
+        }
+        // spaces need their own option
+        let spaces = TEST_DATA.replace("25#@", "25 \r\n#@");
+        assert_eq!(
+            tolerant
+                .clone()
+                .build()
+                .unwrap()
+                .parse_one(spaces.as_bytes()),
+            Err(Error::WhitespaceInNumericField)
+        );
+        let tolerant = tolerant.trim_numeric_spaces(true).build().unwrap();
+        assert!(tolerant.parse_one(spaces.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_numeric_width() {
+        let payload = TEST_PAYLOAD.as_bytes();
+        let fixed = SentinelConfig::new().numeric_width(10).build().unwrap();
+        let frame = fixed.create_sentinelized_stream(payload);
+        assert_eq!(
+            frame,
+            TEST_DATA
+                .replace("=25#", "=0000000025#")
+                .replace("%1925?", "%0000001925?")
+                .into_bytes()
+        );
+        // 8 zeros in the length field, 6 in the checksum field
+        assert_eq!(frame.len(), TEST_DATA.len() + 8 + 6);
+        assert_eq!(fixed.encoded_len(payload), frame.len());
+        assert_eq!(fixed.parse_one(&frame), Ok((payload.to_vec(), frame.len())));
+        // unpadded fields still parse
+        assert!(fixed.parse_one(TEST_DATA.as_bytes()).is_ok());
+        assert_eq!(
+            Sentinelizer::default().parse_one(&frame),
+            Err(Error::InvalidNumericField)
+        );
+
+        // values wider than the field are written in full
+        let fixed = SentinelConfig::new().numeric_width(2).build().unwrap();
+        let frame = fixed.create_sentinelized_stream(payload);
+        assert_eq!(frame, TEST_DATA.as_bytes());
+        let frame = fixed.create_sentinelized_stream(b"");
+        assert!(frame.starts_with(b"+=+=+=+=00#@#@#@#@!%!%!%!%00?^?^?^?^"));
+        assert_eq!(fixed.parse_one(&frame), Ok((Vec::new(), frame.len())));
+
+        let hex = SentinelConfig::new()
+            .radix(16)
+            .numeric_width(4)
+            .build()
+            .unwrap();
+        let frame = hex.create_sentinelized_stream(payload);
+        assert!(frame.starts_with(b"+=+=+=+=0019#@#@#@#@"));
+        assert_eq!(hex.parse_one(&frame), Ok((payload.to_vec(), frame.len())));
+
+        assert_eq!(
+            SentinelConfig::new().numeric_width(21).build(),
+            Err(ConfigError::UnsupportedNumericWidth { width: 21 })
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let sentinelizer = SentinelConfig::new().radix(16).build().unwrap();
+        let frame = sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+        assert_eq!(
+            frame,
+            b"+=+=+=+=19#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%785?^?^?^?^".to_vec()
+        );
+        assert_eq!(
+            sentinelizer.encoded_len(TEST_PAYLOAD.as_bytes()),
+            frame.len()
+        );
+        assert_eq!(
+            sentinelizer.parse_one(&frame),
+            Ok((TEST_PAYLOAD.as_bytes().to_vec(), frame.len()))
+        );
+
+        // 300 bytes summing to 76500, upper case digits are accepted too
+        let payload = vec![0xFF; 300];
+        let frame = sentinelizer.create_sentinelized_stream(&payload);
+        assert!(frame.starts_with(b"+=+=+=+=12c#@#@#@#@"));
+        assert!(frame.ends_with(b"!%!%!%!%12ad4?^?^?^?^"));
+        let mut upper = b"+=+=+=+=12C#@#@#@#@".to_vec();
+        upper.extend_from_slice(&payload);
+        upper.extend_from_slice(b"!%!%!%!%12AD4?^?^?^?^");
+        assert_eq!(sentinelizer.parse_strict(&upper), Ok(payload));
+
+        // decimal frames are misread as hex lengths
+        assert!(sentinelizer.parse_one(TEST_DATA.as_bytes()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    #[tracing_test::traced_test]
+    fn test_checksum_mismatch_event() {
+        let frame = TEST_DATA.replace("1925", "1926");
+        assert_eq!(
+            SentinelConfig::new()
+                .build()
+                .unwrap()
+                .parse_one(frame.as_bytes()),
+            Err(Error::ChecksumVerifyError)
+        );
+        assert!(logs_contain("checksum mismatch"));
+        assert!(logs_contain("declared=1926"));
+        assert!(logs_contain("computed=1925"));
+    }
+}
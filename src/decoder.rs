@@ -0,0 +1,705 @@
+//! Incremental decoding of sentinelized streams that arrive in arbitrary chunks,
+//! such as reads from the OpenUxAS Tcp bridge socket.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use super::checksum::RunningChecksum;
+use super::config::FrameRanges;
+use super::{Error, Result, Sentinelizer};
+
+/// Buffers partial reads and yields complete payloads as they become available.
+///
+/// Decoded frames aren't removed from the buffer one by one, the decoder only
+/// advances past them and compacts the buffer on the next `push_bytes` once the
+/// consumed part is at least as large as the rest. Every byte is thus moved at most
+/// once, keeping the cost linear in the number of bytes however small the frames
+/// are. The buffer holds less than twice the unprocessed bytes plus the pushed
+/// chunk, so when all messages are taken after each push its length stays below
+/// two incomplete frames and the largest chunk. Its capacity is kept for reuse
+/// and not shrunk, so it reflects the largest such size seen.
+///
+/// The checksum of a frame is accumulated as its payload arrives, so the payload
+/// of a large frame is only passed over once rather than on completion.
+#[derive(Default)]
+pub struct SentinelDecoder {
+    sentinelizer: Sentinelizer,
+    buf: Vec<u8>,
+    /// Start of the bytes in `buf` that haven't been processed yet
+    pos: usize,
+    /// Frame whose header has been read, waiting for the rest of the payload
+    pending: Option<PendingFrame>,
+    stats: DecoderStats,
+    on_frame: Option<Box<FrameHook>>,
+    /// Frame whose payload is being returned by `next_part`
+    streaming: Option<Streaming>,
+    stream_threshold: usize,
+}
+
+/// A piece of a frame returned by `SentinelDecoder::next_part`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePart<'a> {
+    /// The next bytes of the payload
+    Chunk(&'a [u8]),
+    /// The payload is complete and matches the checksum
+    End,
+}
+
+/// Progress of the frame `next_part` is returning
+#[derive(Debug)]
+enum Streaming {
+    /// The header has been consumed, the payload bytes are returned as they arrive
+    Payload {
+        remaining: usize,
+        checksum: RunningChecksum,
+        /// Bytes of the frame consumed so far
+        consumed: usize,
+    },
+    /// The payload has been returned completely and verified
+    Verified,
+}
+
+/// Callback invoked with each decoded payload, see `SentinelDecoder::on_frame`
+type FrameHook = dyn FnMut(&[u8]) + Send;
+
+impl fmt::Debug for SentinelDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SentinelDecoder")
+            .field("sentinelizer", &self.sentinelizer)
+            .field("buffered_len", &self.buffered_len())
+            .field("pending", &self.pending)
+            .field("stats", &self.stats)
+            .field("on_frame", &self.on_frame.is_some())
+            .field("streaming", &self.streaming)
+            .field("stream_threshold", &self.stream_threshold)
+            .finish()
+    }
+}
+
+/// Counters accumulated by a `SentinelDecoder` over its lifetime,
+/// to monitor the health of a link without logging every frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Frames decoded successfully
+    pub frames: u64,
+    /// Bytes of the decoded frames, sentinels included
+    pub bytes_consumed: u64,
+    /// Bytes dropped after errors
+    pub bytes_skipped: u64,
+    /// Frames whose payload didn't match the checksum
+    pub checksum_errors: u64,
+    /// Errors due to a missing sentinel
+    pub sentinel_errors: u64,
+    /// All other errors, e.g. malformed numeric fields
+    pub other_errors: u64,
+}
+
+/// Header of the frame at the start of the unprocessed bytes,
+/// with the checksum of the payload bytes received so far
+#[derive(Debug)]
+struct PendingFrame {
+    /// Payload range relative to the start of the unprocessed bytes
+    payload: Range<usize>,
+    checksum: RunningChecksum,
+    /// End of the payload bytes included in the checksum
+    summed: usize,
+}
+
+impl SentinelDecoder {
+    /// Create a decoder with an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a decoder for frames using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(sentinelizer: Sentinelizer) -> Self {
+        SentinelDecoder {
+            sentinelizer,
+            buf: Vec::new(),
+            pos: 0,
+            pending: None,
+            stats: DecoderStats::default(),
+            on_frame: None,
+            streaming: None,
+            stream_threshold: 0,
+        }
+    }
+
+    /// Payloads longer than this are returned by `next_part` in chunks as they
+    /// arrive, shorter ones as a single chunk once the whole frame is there and
+    /// verified. 0 by default, streaming every payload.
+    pub fn set_stream_threshold(&mut self, len: usize) {
+        self.stream_threshold = len;
+    }
+
+    /// Call `f` with every payload `next_message` returns, before it is returned, e.g.
+    /// to check the sequence numbers of the LMCP messages for dropped frames.
+    /// Frames that fail to decode aren't passed. Replaces the previous callback.
+    pub fn on_frame(&mut self, f: impl FnMut(&[u8]) + Send + 'static) {
+        self.on_frame = Some(Box::new(f));
+    }
+
+    /// Append received bytes to the internal buffer
+    pub fn push_bytes(&mut self, data: &[u8]) {
+        if self.pos > 0 && self.pos >= self.buf.len() - self.pos {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Return the next complete payload from the buffer.
+    /// `None` means more bytes are needed, the partial frame stays buffered.
+    /// On `Some(Err(_))` the offending bytes are dropped, up to the next possible
+    /// frame start, so calling again continues with the rest of the stream.
+    pub fn next_message(&mut self) -> Option<Result<Vec<u8>>> {
+        if self.pos == self.buf.len() {
+            return None;
+        }
+        match self.locate_frame() {
+            Ok(FrameRanges { payload, frame_len }) => {
+                let payload = self.buf[self.pos..][payload].to_vec();
+                self.pos += frame_len;
+                self.stats.frames += 1;
+                self.stats.bytes_consumed += frame_len as u64;
+                if let Some(on_frame) = &mut self.on_frame {
+                    on_frame(&payload);
+                }
+                Some(Ok(payload))
+            }
+            Err(Error::Incomplete { .. }) => None,
+            Err(e) => Some(Err(self.skip_after(e))),
+        }
+    }
+
+    /// Return the payload of the next frame in pieces, so a large payload doesn't
+    /// have to be buffered completely: `FramePart::Chunk`s with the payload bytes
+    /// received so far, then `FramePart::End` once the trailer has been checked.
+    /// Payload bytes are dropped from the buffer once they have been returned.
+    /// `None` means more bytes are needed. An error in place of `End` means the
+    /// chunks of the frame were corrupt; as with `next_message`, the offending
+    /// bytes are dropped and calling again continues with the next frame.
+    /// See `set_stream_threshold` for smaller payloads, and `finish` for learning
+    /// that the input ended in the middle of a frame. Frames are either taken
+    /// with `next_part` or with `next_message`, switching in the middle of a
+    /// frame loses it.
+    pub fn next_part(&mut self) -> Option<Result<FramePart<'_>>> {
+        match self.streaming.take() {
+            Some(Streaming::Verified) => return Some(Ok(FramePart::End)),
+            Some(Streaming::Payload {
+                remaining: 0,
+                checksum,
+                consumed,
+            }) => {
+                let data = &self.buf[self.pos..];
+                let verify = self.sentinelizer.verifies_checksum();
+                let res = self.sentinelizer.locate_trailer(
+                    data,
+                    0..0,
+                    false,
+                    verify,
+                    Some(checksum.value()),
+                );
+                return match res {
+                    Ok(FrameRanges { frame_len, .. }) => {
+                        self.pos += frame_len;
+                        self.stats.frames += 1;
+                        self.stats.bytes_consumed += (consumed + frame_len) as u64;
+                        Some(Ok(FramePart::End))
+                    }
+                    Err(Error::Incomplete { .. }) => {
+                        self.streaming = Some(Streaming::Payload {
+                            remaining: 0,
+                            checksum,
+                            consumed,
+                        });
+                        None
+                    }
+                    Err(e) => Some(Err(self.skip_after(e))),
+                };
+            }
+            Some(Streaming::Payload {
+                remaining,
+                mut checksum,
+                consumed,
+            }) => {
+                let start = self.pos;
+                let len = remaining.min(self.buf.len() - start);
+                let chunk = start..start + len;
+                if self.sentinelizer.verifies_checksum() {
+                    checksum.update(&self.buf[chunk.clone()]);
+                }
+                self.pos += len;
+                self.streaming = Some(Streaming::Payload {
+                    remaining: remaining - len,
+                    checksum,
+                    consumed: consumed + len,
+                });
+                if len == 0 {
+                    return None;
+                }
+                return Some(Ok(FramePart::Chunk(&self.buf[chunk])));
+            }
+            None => {}
+        }
+        if self.pos == self.buf.len() {
+            return None;
+        }
+        let data = &self.buf[self.pos..];
+        match self.sentinelizer.locate_header(data) {
+            Ok(payload) if payload.len() > self.stream_threshold => {
+                let len_field = self.sentinelizer.len_field(data, payload.start);
+                self.streaming = Some(Streaming::Payload {
+                    remaining: payload.len(),
+                    checksum: self.sentinelizer.running_checksum(len_field),
+                    consumed: payload.start,
+                });
+                self.pos += payload.start;
+                self.pending = None;
+                self.next_part()
+            }
+            Ok(_) | Err(Error::Incomplete { .. }) => match self.locate_frame() {
+                Ok(FrameRanges { payload, frame_len }) => {
+                    let start = self.pos;
+                    self.pos += frame_len;
+                    self.stats.frames += 1;
+                    self.stats.bytes_consumed += frame_len as u64;
+                    self.streaming = Some(Streaming::Verified);
+                    Some(Ok(FramePart::Chunk(&self.buf[start..][payload])))
+                }
+                Err(Error::Incomplete { .. }) => None,
+                Err(e) => Some(Err(self.skip_after(e))),
+            },
+            Err(e) => Some(Err(self.skip_after(e))),
+        }
+    }
+
+    /// Drop bytes after an error until the buffer starts with something that could
+    /// be a frame header, and count the error
+    fn skip_after(&mut self, e: Error) -> Error {
+        let skipped = self
+            .sentinelizer
+            .next_frame_candidate(&self.buf[self.pos..]);
+        self.pos += skipped;
+        self.stats.bytes_skipped += skipped as u64;
+        match e {
+            Error::ChecksumVerifyError => self.stats.checksum_errors += 1,
+            Error::SentinelNotFound { .. } => self.stats.sentinel_errors += 1,
+            _ => self.stats.other_errors += 1,
+        }
+        e
+    }
+
+    /// Locate the frame at the start of the unprocessed bytes like
+    /// `Sentinelizer::locate_frame`, checksumming the new payload bytes on the way
+    fn locate_frame(&mut self) -> Result<FrameRanges> {
+        let data = &self.buf[self.pos..];
+        let pending = match &mut self.pending {
+            Some(pending) => pending,
+            // too short to be a frame, wait for more before looking at the header
+            None if data.len() < self.sentinelizer.min_frame_len() => {
+                return Err(Error::Incomplete { needed: None })
+            }
+            None => {
+                let payload = self.sentinelizer.locate_header(data)?;
+                let len_field = self.sentinelizer.len_field(data, payload.start);
+                self.pending.insert(PendingFrame {
+                    checksum: self.sentinelizer.running_checksum(len_field),
+                    summed: payload.start,
+                    payload,
+                })
+            }
+        };
+        let available = pending.payload.end.min(data.len());
+        if available > pending.summed && self.sentinelizer.verifies_checksum() {
+            pending.checksum.update(&data[pending.summed..available]);
+            pending.summed = available;
+        }
+        if available < pending.payload.end {
+            return Err(Error::Incomplete { needed: None });
+        }
+        let res = self.sentinelizer.locate_trailer(
+            data,
+            pending.payload.clone(),
+            false,
+            self.sentinelizer.verifies_checksum(),
+            Some(pending.checksum.value()),
+        );
+        if !matches!(res, Err(Error::Incomplete { .. })) {
+            self.pending = None;
+        }
+        res
+    }
+
+    /// Counters of the frames and errors seen so far, they are kept by `clear`
+    pub fn stats(&self) -> &DecoderStats {
+        &self.stats
+    }
+
+    /// Drop all buffered bytes, e.g. when the connection has been reset
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.pos = 0;
+        self.pending = None;
+        self.streaming = None;
+    }
+
+    /// Number of buffered bytes that don't form a complete frame yet
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Call once the input has ended and `next_message` or `next_part` returns `None`:
+    /// report the unfinished frame, if any, as `Error::Incomplete` and drop it, so that
+    /// the next call returns `None` as on a frame boundary. A frame whose payload
+    /// `next_part` has started to return counts even when no bytes are buffered.
+    pub fn finish(&mut self) -> Option<Error> {
+        let streaming = matches!(self.streaming, Some(Streaming::Payload { .. }));
+        if self.buffered_len() == 0 && !streaming && self.pending.is_none() {
+            return None;
+        }
+        self.clear();
+        Some(Error::Incomplete { needed: None })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LmcpSentinelizer, SentinelKind};
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_byte_at_a_time() {
+        let mut decoder = SentinelDecoder::new();
+        let mut messages = vec![];
+        for b in TEST_DATA.as_bytes() {
+            decoder.push_bytes(&[*b]);
+            while let Some(msg) = decoder.next_message() {
+                messages.push(msg);
+            }
+        }
+        assert_eq!(messages, vec![Ok(TEST_PAYLOAD.as_bytes().to_vec())]);
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_on_frame() {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut decoder = SentinelDecoder::new();
+        let hook_seen = Arc::clone(&seen);
+        decoder.on_frame(move |payload| hook_seen.lock().unwrap().push(payload.to_vec()));
+
+        let mut data = TEST_DATA.as_bytes().to_vec();
+        data.extend_from_slice(&TEST_DATA.replace("1925", "1926").into_bytes());
+        data.extend_from_slice(&LmcpSentinelizer::create_sentinelized_stream(b"second"));
+        data.extend_from_slice(&TEST_DATA.as_bytes()[..30]);
+        for chunk in data.chunks(7) {
+            decoder.push_bytes(chunk);
+            while let Some(msg) = decoder.next_message() {
+                let seen = seen.lock().unwrap();
+                if let Ok(payload) = msg {
+                    assert_eq!(seen.last(), Some(&payload));
+                }
+            }
+        }
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![TEST_PAYLOAD.as_bytes().to_vec(), b"second".to_vec()]
+        );
+        assert_eq!(decoder.stats().frames, 2);
+    }
+
+    #[test]
+    fn test_on_frame_cell_state() {
+        use core::cell::Cell;
+
+        // a per-connection sequence number, the hook doesn't have to be `Sync`
+        let next = Cell::new(b'0');
+        let mut decoder = SentinelDecoder::new();
+        decoder.on_frame(move |payload| {
+            assert_eq!(payload, [next.get()]);
+            next.set(next.get() + 1);
+        });
+        for seq in b'0'..b'3' {
+            decoder.push_bytes(&LmcpSentinelizer::create_sentinelized_stream(&[seq]));
+        }
+        while let Some(msg) = decoder.next_message() {
+            msg.unwrap();
+        }
+        assert_eq!(decoder.stats().frames, 3);
+    }
+
+    #[test]
+    fn test_next_part_streams_large_payload() {
+        let payload: Vec<u8> = (0..300_000u32).map(|x| (x % 251) as u8).collect();
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&payload);
+        let mut data = frame.clone();
+        data.extend_from_slice(TEST_DATA.as_bytes());
+
+        let mut decoder = SentinelDecoder::new();
+        decoder.set_stream_threshold(1000);
+        let mut received = Vec::new();
+        let mut parts = Vec::new();
+        for chunk in data.chunks(data.len() / 3 + 1) {
+            decoder.push_bytes(chunk);
+            while let Some(part) = decoder.next_part() {
+                match part.unwrap() {
+                    FramePart::Chunk(chunk) => received.extend_from_slice(chunk),
+                    FramePart::End => parts.push(core::mem::take(&mut received)),
+                }
+            }
+            // the streamed payload bytes aren't kept
+            assert!(decoder.buffered_len() < frame.len() - payload.len() + TEST_DATA.len());
+        }
+        assert_eq!(parts, vec![payload, TEST_PAYLOAD.as_bytes().to_vec()]);
+        assert!(received.is_empty());
+        assert_eq!(decoder.stats().frames, 2);
+        assert_eq!(decoder.stats().bytes_consumed, data.len() as u64);
+    }
+
+    #[test]
+    fn test_next_part_corrupt_payload() {
+        let corrupted = TEST_DATA.replace("1925", "1926");
+        let mut decoder = SentinelDecoder::new();
+        decoder.push_bytes(corrupted.as_bytes());
+        decoder.push_bytes(TEST_DATA.as_bytes());
+        assert_eq!(
+            decoder.next_part(),
+            Some(Ok(FramePart::Chunk(TEST_PAYLOAD.as_bytes())))
+        );
+        assert_eq!(decoder.next_part(), Some(Err(Error::ChecksumVerifyError)));
+        assert_eq!(
+            decoder.next_part(),
+            Some(Ok(FramePart::Chunk(TEST_PAYLOAD.as_bytes())))
+        );
+        assert_eq!(decoder.next_part(), Some(Ok(FramePart::End)));
+        assert_eq!(decoder.next_part(), None);
+
+        // below the threshold the frame is verified before anything is returned
+        decoder.set_stream_threshold(100);
+        decoder.push_bytes(corrupted.as_bytes());
+        assert_eq!(decoder.next_part(), Some(Err(Error::ChecksumVerifyError)));
+        assert_eq!(decoder.next_part(), None);
+    }
+
+    #[test]
+    fn test_next_part_truncated_payload() {
+        let payload: Vec<u8> = (0..10_000u32).map(|x| (x % 251) as u8).collect();
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&payload);
+        let mut decoder = SentinelDecoder::new();
+        let mut received = Vec::new();
+        // the connection drops before the end of the payload
+        for chunk in frame[..5000].chunks(1000) {
+            decoder.push_bytes(chunk);
+            while let Some(part) = decoder.next_part() {
+                match part.unwrap() {
+                    FramePart::Chunk(chunk) => received.extend_from_slice(chunk),
+                    FramePart::End => panic!("truncated frame ended"),
+                }
+            }
+        }
+        assert!(received.len() > 1000);
+        assert!(payload.starts_with(&received));
+        assert_eq!(decoder.finish(), Some(Error::Incomplete { needed: None }));
+        assert_eq!(decoder.next_part(), None);
+        assert_eq!(decoder.stats().frames, 0);
+    }
+
+    #[test]
+    fn test_two_and_a_half_frames() {
+        let frame = TEST_DATA.as_bytes();
+        let half = frame.len() / 2;
+        let mut data = frame.repeat(2);
+        data.extend_from_slice(&frame[..half]);
+
+        let mut decoder = SentinelDecoder::new();
+        decoder.push_bytes(&data);
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        assert_eq!(decoder.next_message(), None);
+        assert_eq!(decoder.buffered_len(), half);
+
+        decoder.push_bytes(&frame[half..]);
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        assert_eq!(decoder.next_message(), None);
+    }
+
+    #[test]
+    fn test_many_small_frames() {
+        let frame = LmcpSentinelizer::create_sentinelized_stream(b"x");
+        let data = frame.repeat(10_000);
+
+        // whole frames as well as chunks straddling them
+        for &chunk_len in &[frame.len(), 7, frame.len() + 5] {
+            let mut decoder = SentinelDecoder::new();
+            let mut count = 0;
+            for chunk in data.chunks(chunk_len) {
+                decoder.push_bytes(chunk);
+                while let Some(msg) = decoder.next_message() {
+                    assert_eq!(msg, Ok(b"x".to_vec()));
+                    count += 1;
+                }
+                assert!(decoder.buf.len() <= 2 * frame.len() + chunk_len);
+            }
+            assert_eq!(count, 10_000);
+            assert_eq!(decoder.buffered_len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_incremental_checksum() {
+        let payload: Vec<u8> = (0..10_000u32).map(|x| (x * 7) as u8).collect();
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&payload);
+
+        let mut decoder = SentinelDecoder::new();
+        let mut messages = vec![];
+        for chunk in frame.chunks(333) {
+            decoder.push_bytes(chunk);
+            if let Some(msg) = decoder.next_message() {
+                messages.push(msg);
+            } else if let Some(pending) = &decoder.pending {
+                let summed = &payload[..pending.summed - pending.payload.start];
+                assert_eq!(pending.checksum.value(), LmcpSentinelizer::checksum(summed));
+            }
+        }
+        assert_eq!(messages, vec![Ok(payload.clone())]);
+        assert!(decoder.pending.is_none());
+
+        // a mismatch is still caught with the accumulated checksum
+        let mut frame = frame;
+        let mid = frame.len() / 2;
+        frame[mid] ^= 1;
+        for chunk in frame.chunks(333) {
+            decoder.push_bytes(chunk);
+        }
+        assert_eq!(
+            decoder.next_message(),
+            Some(Err(Error::ChecksumVerifyError))
+        );
+    }
+
+    #[test]
+    fn test_garbage_between_frames() {
+        let mut data = TEST_DATA.as_bytes().to_vec();
+        data.extend_from_slice(b"garbage+=");
+        data.extend_from_slice(TEST_DATA.as_bytes());
+
+        let mut decoder = SentinelDecoder::new();
+        decoder.push_bytes(&data);
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        assert_eq!(
+            decoder.next_message(),
+            Some(Err(Error::SentinelNotFound {
+                which: SentinelKind::BeforePayloadSize
+            }))
+        );
+        assert_eq!(
+            decoder.next_message(),
+            Some(Err(Error::InvalidNumericField))
+        );
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+        assert_eq!(decoder.next_message(), None);
+    }
+
+    #[test]
+    fn test_short_garbage_waits() {
+        let mut decoder = SentinelDecoder::new();
+        decoder.push_bytes(b"garbage");
+        assert_eq!(decoder.next_message(), None);
+        assert_eq!(decoder.buffered_len(), 7);
+
+        decoder.push_bytes(TEST_DATA.as_bytes());
+        assert!(matches!(decoder.next_message(), Some(Err(_))));
+        assert_eq!(
+            decoder.next_message(),
+            Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let frame = TEST_DATA.as_bytes();
+        let corrupted = TEST_DATA.replace("1925", "1926");
+        let mut data = frame.to_vec();
+        data.extend_from_slice(corrupted.as_bytes());
+        data.extend_from_slice(b"garbage");
+        data.extend_from_slice(frame);
+        data.extend_from_slice(&frame[..20]);
+
+        let mut decoder = SentinelDecoder::new();
+        for chunk in data.chunks(10) {
+            decoder.push_bytes(chunk);
+            while decoder.next_message().is_some() {}
+        }
+        assert_eq!(
+            *decoder.stats(),
+            DecoderStats {
+                frames: 2,
+                bytes_consumed: 2 * frame.len() as u64,
+                bytes_skipped: (corrupted.len() + 7) as u64,
+                checksum_errors: 1,
+                sentinel_errors: 1,
+                other_errors: 0,
+            }
+        );
+        assert_eq!(decoder.buffered_len(), 20);
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut decoder = SentinelDecoder::new();
+        assert_eq!(decoder.finish(), None);
+
+        decoder.push_bytes(TEST_DATA.as_bytes());
+        decoder.push_bytes(&TEST_DATA.as_bytes()[..20]);
+        assert!(decoder.next_message().is_some());
+        assert!(decoder.next_message().is_none());
+        assert_eq!(decoder.finish(), Some(Error::Incomplete { needed: None }));
+        assert_eq!(decoder.buffered_len(), 0);
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn test_finish_streamed_frame() {
+        let frame = LmcpSentinelizer::create_sentinelized_stream(&[b'x'; 100]);
+        let mut decoder = SentinelDecoder::new();
+        decoder.push_bytes(&frame[..50]);
+        while let Some(part) = decoder.next_part() {
+            assert!(matches!(part, Ok(FramePart::Chunk(_))));
+        }
+        // the payload bytes have been handed out, the frame is still unfinished
+        assert_eq!(decoder.buffered_len(), 0);
+        assert_eq!(decoder.finish(), Some(Error::Incomplete { needed: None }));
+        assert_eq!(decoder.finish(), None);
+
+        decoder.push_bytes(TEST_DATA.as_bytes());
+        assert_eq!(
+            decoder.next_part(),
+            Some(Ok(FramePart::Chunk(TEST_PAYLOAD.as_bytes())))
+        );
+        assert_eq!(decoder.next_part(), Some(Ok(FramePart::End)));
+        assert_eq!(decoder.next_part(), None);
+    }
+}
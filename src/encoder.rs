@@ -0,0 +1,140 @@
+//! Encoding of payloads that are produced in pieces, e.g. by a serializer
+//! writing one LMCP field at a time.
+//!
+//! The frame header carries the payload length, so nothing can be written before
+//! the last chunk is known: `SentinelEncoder` keeps the payload in memory until
+//! `finish`, the same as `create_sentinelized_stream` does. What it saves is the
+//! separate pass over the payload for the checksum, which is accumulated as the
+//! chunks come in, and the need for the producer to assemble the payload itself.
+//! A sender that knows the length upfront gains nothing from the encoder and can
+//! use `write_to` with the complete payload instead.
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use super::checksum::RunningChecksum;
+use super::Sentinelizer;
+
+/// Collects payload chunks and writes them as a single frame
+#[derive(Debug, Clone)]
+pub struct SentinelEncoder {
+    sentinelizer: Sentinelizer,
+    payload: Vec<u8>,
+    checksum: RunningChecksum,
+}
+
+impl Default for SentinelEncoder {
+    fn default() -> Self {
+        Self::with_sentinelizer(Sentinelizer::default())
+    }
+}
+
+impl SentinelEncoder {
+    /// Create an encoder for a frame with the default sentinels
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an encoder for a frame using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(sentinelizer: Sentinelizer) -> Self {
+        let checksum = RunningChecksum::new(sentinelizer.checksum_algorithm());
+        SentinelEncoder {
+            sentinelizer,
+            payload: Vec::new(),
+            checksum,
+        }
+    }
+
+    /// Append a chunk to the payload
+    pub fn update(&mut self, data: &[u8]) {
+        self.checksum.update(data);
+        self.payload.extend_from_slice(data);
+    }
+
+    /// Length of the payload collected so far
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Check whether no payload bytes have been collected yet
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// Checksum of the trailer for the payload collected so far
+    pub fn checksum(&self) -> u32 {
+        if self.sentinelizer.checksums_payload_only() {
+            self.checksum.value()
+        } else {
+            // the length field comes first, it's only known now
+            self.sentinelizer.checksum(&self.payload)
+        }
+    }
+
+    /// Write the header, the collected payload and the trailer to `w`
+    #[cfg(feature = "std")]
+    pub fn finish<W: Write>(self, w: &mut W) -> io::Result<()> {
+        self.sentinelizer
+            .write_to_with_checksum(&self.payload, self.checksum(), w)
+    }
+
+    /// Append the frame to `out` instead of writing it
+    pub fn finish_into(self, out: &mut Vec<u8>) {
+        self.sentinelizer
+            .write_sentinelized_with_checksum(&self.payload, self.checksum(), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Checksum, LmcpSentinelizer, SentinelConfig};
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_chunked_matches_one_shot() {
+        for chunk_len in 1..=TEST_PAYLOAD.len() {
+            let mut encoder = SentinelEncoder::new();
+            for chunk in TEST_PAYLOAD.as_bytes().chunks(chunk_len) {
+                encoder.update(chunk);
+            }
+            assert_eq!(encoder.len(), TEST_PAYLOAD.len());
+            assert_eq!(encoder.checksum(), 1925);
+
+            let mut frame = vec![];
+            encoder.finish(&mut frame).unwrap();
+            assert_eq!(frame, TEST_DATA.as_bytes().to_vec());
+        }
+    }
+
+    #[test]
+    fn test_empty_and_configured() {
+        let mut frame = vec![];
+        SentinelEncoder::new().finish_into(&mut frame);
+        assert_eq!(frame, LmcpSentinelizer::create_sentinelized_stream(&[]));
+
+        let mut encoder = SentinelEncoder::new();
+        encoder.update(TEST_PAYLOAD.as_bytes());
+        let mut frame = b"previous".to_vec();
+        encoder.finish_into(&mut frame);
+        assert_eq!(&frame[8..], TEST_DATA.as_bytes());
+
+        let sentinelizer = SentinelConfig::new()
+            .checksum(Checksum::Crc32)
+            .build()
+            .unwrap();
+        let mut encoder = sentinelizer.encoder();
+        encoder.update(b"ABCDEFGHIJKL");
+        encoder.update(b"MNOPQRSTUVWXY");
+        let mut frame = vec![];
+        encoder.finish_into(&mut frame);
+        assert_eq!(
+            frame,
+            sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes())
+        );
+    }
+}
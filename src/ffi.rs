@@ -0,0 +1,252 @@
+//! C interface for linking the sentinelizer into the OpenUxAS C++ code,
+//! in place of `UxAS_SentinelSerialBuffer`.
+//!
+//! The functions never panic across the boundary, failures are returned as one
+//! of the `LMCP_SENTINEL_*` status codes. The header is generated with cbindgen,
+//! `cbindgen --config cbindgen.toml --output include/lmcp_sentinelizer.h src/ffi.rs`.
+//!
+//! The C libraries are built with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or `cdylib`),
+//! the crate types aren't set in the manifest as they can't depend on a feature
+//! and would break the `no_std` build.
+
+use core::slice;
+
+use super::{Error, FrameRanges, LmcpSentinelizer, Sentinelizer};
+
+/// The call succeeded
+pub const LMCP_SENTINEL_OK: i32 = 0;
+/// A pointer argument was null
+pub const LMCP_SENTINEL_NULL_POINTER: i32 = -1;
+/// The output buffer is too small, `out_len` holds the required size
+pub const LMCP_SENTINEL_BUFFER_TOO_SMALL: i32 = -2;
+/// The input is an incomplete frame, more bytes are needed
+pub const LMCP_SENTINEL_INCOMPLETE: i32 = -3;
+/// See `Error::SentinelNotFound`
+pub const LMCP_SENTINEL_SENTINEL_NOT_FOUND: i32 = -4;
+/// See `Error::ChecksumVerifyError`
+pub const LMCP_SENTINEL_CHECKSUM_ERROR: i32 = -5;
+/// See `Error::InvalidNumericField`
+pub const LMCP_SENTINEL_INVALID_NUMERIC_FIELD: i32 = -6;
+/// See `Error::LengthMismatch`
+pub const LMCP_SENTINEL_LENGTH_MISMATCH: i32 = -7;
+/// See `Error::PayloadTooLarge`
+pub const LMCP_SENTINEL_PAYLOAD_TOO_LARGE: i32 = -8;
+/// See `Error::WhitespaceInNumericField`
+pub const LMCP_SENTINEL_WHITESPACE_IN_NUMERIC_FIELD: i32 = -9;
+/// See `Error::TrailingBytes`
+pub const LMCP_SENTINEL_TRAILING_BYTES: i32 = -10;
+/// See `Error::MalformedFrame`
+pub const LMCP_SENTINEL_MALFORMED_FRAME: i32 = -11;
+/// See `Error::ResyncLimitExceeded`
+pub const LMCP_SENTINEL_RESYNC_LIMIT_EXCEEDED: i32 = -12;
+/// See `Error::UnexpectedEof`
+pub const LMCP_SENTINEL_UNEXPECTED_EOF: i32 = -13;
+/// See `Error::MisplacedChecksumSentinel`
+pub const LMCP_SENTINEL_MISPLACED_CHECKSUM_SENTINEL: i32 = -14;
+
+fn status(e: Error) -> i32 {
+    match e {
+        Error::Incomplete { .. } => LMCP_SENTINEL_INCOMPLETE,
+        Error::SentinelNotFound { .. } => LMCP_SENTINEL_SENTINEL_NOT_FOUND,
+        Error::ChecksumVerifyError => LMCP_SENTINEL_CHECKSUM_ERROR,
+        Error::InvalidNumericField => LMCP_SENTINEL_INVALID_NUMERIC_FIELD,
+        Error::LengthMismatch { .. } => LMCP_SENTINEL_LENGTH_MISMATCH,
+        Error::PayloadTooLarge => LMCP_SENTINEL_PAYLOAD_TOO_LARGE,
+        Error::WhitespaceInNumericField => LMCP_SENTINEL_WHITESPACE_IN_NUMERIC_FIELD,
+        Error::TrailingBytes => LMCP_SENTINEL_TRAILING_BYTES,
+        Error::MalformedFrame { .. } => LMCP_SENTINEL_MALFORMED_FRAME,
+        Error::ResyncLimitExceeded => LMCP_SENTINEL_RESYNC_LIMIT_EXCEEDED,
+        Error::UnexpectedEof => LMCP_SENTINEL_UNEXPECTED_EOF,
+        Error::MisplacedChecksumSentinel { .. } => LMCP_SENTINEL_MISPLACED_CHECKSUM_SENTINEL,
+    }
+}
+
+/// View a C buffer as a slice, a null pointer is accepted for an empty buffer
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Copy `data` to the output buffer, or report the size it needs
+unsafe fn output(data: &[u8], out: *mut u8, out_cap: usize, out_len: *mut usize) -> i32 {
+    *out_len = data.len();
+    if out_cap < data.len() {
+        return LMCP_SENTINEL_BUFFER_TOO_SMALL;
+    }
+    if !data.is_empty() {
+        if out.is_null() {
+            return LMCP_SENTINEL_NULL_POINTER;
+        }
+        slice::from_raw_parts_mut(out, data.len()).copy_from_slice(data);
+    }
+    LMCP_SENTINEL_OK
+}
+
+/// Number of bytes `len` bytes of payload at `data` occupy once sentinelized
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, it may be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn lmcp_sentinel_encoded_len(data: *const u8, len: usize) -> usize {
+    input(data, len).map_or(0, LmcpSentinelizer::encoded_len)
+}
+
+/// Sentinelize the `len` bytes of payload at `data` into the `out_cap` bytes at `out`.
+/// The frame length is stored in `out_len`, also when `out` is too small, so the
+/// required size can be queried by passing a capacity of 0.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to `out_cap` writable bytes,
+/// either may be null if its length is 0. `out_len` must point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn lmcp_sentinel_encode(
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> i32 {
+    let data = match input(data, len) {
+        Some(data) if !out_len.is_null() => data,
+        _ => return LMCP_SENTINEL_NULL_POINTER,
+    };
+    let frame = LmcpSentinelizer::create_sentinelized_stream(data);
+    output(&frame, out, out_cap, out_len)
+}
+
+/// Decode the frame at the beginning of the `len` bytes at `data`, copy its payload
+/// into the `out_cap` bytes at `out` and store the payload length in `out_len` and
+/// the number of bytes the frame occupies in `consumed`. On
+/// `LMCP_SENTINEL_INCOMPLETE` the caller should retry once more bytes arrived.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to `out_cap` writable bytes,
+/// either may be null if its length is 0. `out_len` and `consumed` must point to
+/// writable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn lmcp_sentinel_decode(
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+    consumed: *mut usize,
+) -> i32 {
+    let data = match input(data, len) {
+        Some(data) if !out_len.is_null() && !consumed.is_null() => data,
+        _ => return LMCP_SENTINEL_NULL_POINTER,
+    };
+    *consumed = 0;
+    match Sentinelizer::default().locate_frame(data) {
+        Ok(FrameRanges { payload, frame_len }) => {
+            let res = output(&data[payload], out, out_cap, out_len);
+            if res == LMCP_SENTINEL_OK {
+                *consumed = frame_len;
+            }
+            res
+        }
+        Err(e) => status(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::ptr;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_encode() {
+        let payload = TEST_PAYLOAD.as_bytes();
+        let mut out_len = 0;
+        let res = unsafe {
+            lmcp_sentinel_encode(
+                payload.as_ptr(),
+                payload.len(),
+                ptr::null_mut(),
+                0,
+                &mut out_len,
+            )
+        };
+        assert_eq!(res, LMCP_SENTINEL_BUFFER_TOO_SMALL);
+        assert_eq!(out_len, TEST_DATA.len());
+        assert_eq!(
+            unsafe { lmcp_sentinel_encoded_len(payload.as_ptr(), payload.len()) },
+            out_len
+        );
+
+        let mut out = vec![0; out_len];
+        let res = unsafe {
+            lmcp_sentinel_encode(
+                payload.as_ptr(),
+                payload.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut out_len,
+            )
+        };
+        assert_eq!(res, LMCP_SENTINEL_OK);
+        assert_eq!(out, TEST_DATA.as_bytes());
+
+        let res = unsafe { lmcp_sentinel_encode(ptr::null(), 1, ptr::null_mut(), 0, &mut out_len) };
+        assert_eq!(res, LMCP_SENTINEL_NULL_POINTER);
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut data = TEST_DATA.as_bytes().to_vec();
+        data.extend_from_slice(b"+=+=");
+        let mut out = [0u8; 64];
+        let (mut out_len, mut consumed) = (0, 0);
+        let res = unsafe {
+            lmcp_sentinel_decode(
+                data.as_ptr(),
+                data.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut out_len,
+                &mut consumed,
+            )
+        };
+        assert_eq!(res, LMCP_SENTINEL_OK);
+        assert_eq!(&out[..out_len], TEST_PAYLOAD.as_bytes());
+        assert_eq!(consumed, TEST_DATA.len());
+
+        let res = unsafe {
+            lmcp_sentinel_decode(
+                data[consumed..].as_ptr(),
+                data.len() - consumed,
+                out.as_mut_ptr(),
+                out.len(),
+                &mut out_len,
+                &mut consumed,
+            )
+        };
+        assert_eq!(res, LMCP_SENTINEL_INCOMPLETE);
+        assert_eq!(consumed, 0);
+
+        let corrupted = TEST_DATA.replace("1925", "1926");
+        let res = unsafe {
+            lmcp_sentinel_decode(
+                corrupted.as_ptr(),
+                corrupted.len(),
+                out.as_mut_ptr(),
+                out.len(),
+                &mut out_len,
+                &mut consumed,
+            )
+        };
+        assert_eq!(res, LMCP_SENTINEL_CHECKSUM_ERROR);
+    }
+}
@@ -0,0 +1,114 @@
+//! Lazy iteration over the frames of a buffer
+
+use super::config::FrameRanges;
+use super::{Result, Sentinelizer};
+
+/// Iterator over the payloads of consecutive frames in a buffer, borrowing the buffer.
+/// It stops after the last frame or after the first error, e.g. when the
+/// trailing bytes are a truncated frame.
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    sentinelizer: Sentinelizer,
+    data: &'a [u8],
+    /// Offset of `data` in the original buffer
+    offset: usize,
+}
+
+impl<'a> Frames<'a> {
+    pub(crate) fn new(sentinelizer: Sentinelizer, data: &'a [u8]) -> Self {
+        Frames {
+            sentinelizer,
+            data,
+            offset: 0,
+        }
+    }
+
+    /// The bytes that haven't been processed yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Offset of the remaining bytes in the buffer, i.e. where the frame returned
+    /// by the next call to `next` begins
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = self.data;
+        match self.sentinelizer.locate_frame(data) {
+            Ok(FrameRanges { payload, frame_len }) => {
+                self.data = &data[frame_len..];
+                self.offset += frame_len;
+                Some(Ok(&data[payload]))
+            }
+            Err(e) => {
+                // the rest of the buffer can't be framed
+                self.data = &[];
+                self.offset += data.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Error, LmcpSentinelizer};
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_three_frames() {
+        let data = TEST_DATA.repeat(3);
+        let payloads: Result<Vec<_>, _> = LmcpSentinelizer::frames(data.as_bytes()).collect();
+        assert_eq!(payloads.unwrap(), vec![TEST_PAYLOAD.as_bytes(); 3]);
+    }
+
+    #[test]
+    fn test_frames_with_offsets() {
+        let second = LmcpSentinelizer::create_sentinelized_stream(b"second");
+        let mut data = TEST_DATA.as_bytes().to_vec();
+        data.extend_from_slice(&second);
+        data.extend_from_slice(b"garbage");
+
+        let frames: Vec<_> = LmcpSentinelizer::frames_with_offsets(&data).collect();
+        assert_eq!(
+            frames,
+            vec![
+                (Ok(TEST_PAYLOAD.as_bytes()), 0),
+                (Ok(&b"second"[..]), TEST_DATA.len()),
+                (
+                    Err(Error::SentinelNotFound {
+                        which: crate::SentinelKind::BeforePayloadSize
+                    }),
+                    TEST_DATA.len() + second.len()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_last_frame() {
+        let mut data = TEST_DATA.repeat(2);
+        data.push_str(&TEST_DATA[..50]);
+        let mut frames = LmcpSentinelizer::frames(data.as_bytes());
+        assert_eq!(frames.next(), Some(Ok(TEST_PAYLOAD.as_bytes())));
+        assert_eq!(frames.next(), Some(Ok(TEST_PAYLOAD.as_bytes())));
+        assert_eq!(frames.remaining(), &TEST_DATA.as_bytes()[..50]);
+        assert_eq!(
+            frames.next(),
+            Some(Err(Error::Incomplete { needed: Some(10) }))
+        );
+        assert_eq!(frames.next(), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Blocking decoding of payloads read from a `std::io::Read`, e.g. a `TcpStream`
+//! or stdin, the counterpart of `SentinelStream` for synchronous code
+
+use std::io::{self, Read};
+
+use super::{SentinelDecoder, Sentinelizer};
+
+/// Size of the chunks read from the reader
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+/// Reads sentinelized bytes from `R` and returns the decoded payloads.
+///
+/// The reader is only read from when the buffered bytes don't contain another
+/// frame. Corrupt frames are returned as `InvalidData` errors and skipped, reading
+/// again continues with the next frame. A partial frame at EOF is reported as an
+/// `UnexpectedEof` error, after which the reader is exhausted.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    decoder: SentinelDecoder,
+    chunk: Box<[u8]>,
+    eof: bool,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Decode frames with the default sentinels from the reader
+    pub fn new(reader: R) -> Self {
+        Self::with_sentinelizer(reader, Sentinelizer::default())
+    }
+
+    /// Decode frames using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(reader: R, sentinelizer: Sentinelizer) -> Self {
+        FrameReader {
+            reader,
+            decoder: SentinelDecoder::with_sentinelizer(sentinelizer),
+            chunk: vec![0; READ_CHUNK_LEN].into_boxed_slice(),
+            eof: false,
+        }
+    }
+
+    /// Read until the next frame is complete and return its payload,
+    /// or `None` at EOF on a frame boundary
+    pub fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(msg) = self.decoder.next_message() {
+                return msg.map(Some).map_err(io::Error::from);
+            }
+            if self.eof {
+                return self.decoder.finish().map_or(Ok(None), |e| Err(e.into()));
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.decoder.push_bytes(&self.chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Return the underlying reader, dropping any buffered bytes
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_two_frames() {
+        let mut data = TEST_DATA.as_bytes().to_vec();
+        data.extend_from_slice(&crate::LmcpSentinelizer::create_sentinelized_stream(b"x"));
+
+        let mut reader = FrameReader::new(Cursor::new(data));
+        assert_eq!(
+            reader.read_frame().unwrap(),
+            Some(TEST_PAYLOAD.as_bytes().to_vec())
+        );
+        assert_eq!(reader.read_frame().unwrap(), Some(b"x".to_vec()));
+        assert_eq!(reader.read_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn test_partial_frame_at_eof() {
+        let data = TEST_DATA.repeat(2);
+        let reader = FrameReader::new(&data.as_bytes()[..TEST_DATA.len() + 30]);
+        let frames: Vec<_> = reader.collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[0].as_ref().unwrap(),
+            &TEST_PAYLOAD.as_bytes().to_vec()
+        );
+        assert_eq!(
+            frames[1].as_ref().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
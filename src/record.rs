@@ -0,0 +1,124 @@
+//! Recording of raw frames to a file and replaying them, e.g. to capture live
+//! traffic once and feed it through a decoder deterministically in tests.
+//!
+//! The format is a plain sequence of records, each a little endian `u32` with the
+//! length of the frame followed by the frame bytes, sentinels included.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// Writes frames as length prefixed records
+#[derive(Debug)]
+pub struct FrameRecorder<W> {
+    writer: W,
+}
+
+impl<W: Write> FrameRecorder<W> {
+    /// Record frames to the writer
+    pub fn new(writer: W) -> Self {
+        FrameRecorder { writer }
+    }
+
+    /// Append one frame. Frames are recorded as they are, they don't have to be
+    /// valid, e.g. to keep corrupted frames seen on the wire.
+    pub fn record(&mut self, frame: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(frame.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "frame too large to record")
+        })?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(frame)
+    }
+
+    /// Flush the writer and return it
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads back the frames written by a `FrameRecorder`, in order
+#[derive(Debug)]
+pub struct FrameReplay<R> {
+    reader: R,
+}
+
+impl<R: Read> FrameReplay<R> {
+    /// Replay the frames recorded in the reader
+    pub fn new(reader: R) -> Self {
+        FrameReplay { reader }
+    }
+
+    /// Read the next recorded frame, or `None` at the end of the recording.
+    /// A recording that ends inside a record is an `UnexpectedEof` error.
+    pub fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut len = [0; 4];
+        let mut filled = 0;
+        while filled < len.len() {
+            match self.reader.read(&mut len[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let mut frame = Vec::new();
+        let len = u64::from(u32::from_le_bytes(len));
+        (&mut self.reader).take(len).read_to_end(&mut frame)?;
+        if (frame.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(Some(frame))
+    }
+
+    /// Return the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for FrameReplay<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LmcpSentinelizer, SentinelDecoder};
+
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_record_and_replay() {
+        let payloads: [&[u8]; 3] = [TEST_PAYLOAD.as_bytes(), b"", &[0xFF; 1000]];
+        let mut recorder = FrameRecorder::new(Vec::new());
+        for payload in &payloads {
+            recorder
+                .record(&LmcpSentinelizer::create_sentinelized_stream(payload))
+                .unwrap();
+        }
+        let recording = recorder.into_inner().unwrap();
+
+        let mut decoder = SentinelDecoder::new();
+        for frame in FrameReplay::new(&recording[..]) {
+            decoder.push_bytes(&frame.unwrap());
+        }
+        for payload in &payloads {
+            assert_eq!(decoder.next_message(), Some(Ok(payload.to_vec())));
+        }
+        assert_eq!(decoder.next_message(), None);
+
+        // cut off inside the last record
+        let mut replay = FrameReplay::new(&recording[..recording.len() - 1]);
+        assert!(replay.next_frame().unwrap().is_some());
+        assert!(replay.next_frame().unwrap().is_some());
+        assert_eq!(
+            replay.next_frame().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Trait over the framing operations, so code sending or receiving LMCP messages
+//! can be generic over the framing and use a fake in tests
+
+use alloc::vec::Vec;
+
+use super::{LmcpSentinelizer, Result, Sentinelizer};
+
+/// Frames payloads and parses them back, implemented by `LmcpSentinelizer` and
+/// configured `Sentinelizer`s.
+pub trait Sentinelize {
+    /// Frame the payload
+    fn create(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Process one frame at the beginning of the data, return a tuple (payload, consumed)
+    /// with the payload data and the number of bytes the whole frame occupies
+    fn parse(&self, data: &[u8]) -> Result<(Vec<u8>, usize)>;
+}
+
+impl Sentinelize for LmcpSentinelizer {
+    fn create(&self, data: &[u8]) -> Vec<u8> {
+        LmcpSentinelizer::create_sentinelized_stream(data)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<(Vec<u8>, usize)> {
+        LmcpSentinelizer::parse_one(data)
+    }
+}
+
+impl Sentinelize for Sentinelizer {
+    fn create(&self, data: &[u8]) -> Vec<u8> {
+        self.create_sentinelized_stream(data)
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<(Vec<u8>, usize)> {
+        self.parse_one(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SentinelConfig;
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    /// Passes payloads through unchanged, e.g. to look at the raw messages
+    struct Identity;
+
+    impl Sentinelize for Identity {
+        fn create(&self, data: &[u8]) -> Vec<u8> {
+            data.to_vec()
+        }
+
+        fn parse(&self, data: &[u8]) -> Result<(Vec<u8>, usize)> {
+            Ok((data.to_vec(), data.len()))
+        }
+    }
+
+    fn echo(framing: &impl Sentinelize, payload: &[u8]) -> Vec<u8> {
+        let frame = framing.create(payload);
+        framing.parse(&frame).unwrap().0
+    }
+
+    #[test]
+    fn test_implementors() {
+        let payload = TEST_PAYLOAD.as_bytes();
+        assert_eq!(
+            LmcpSentinelizer.create(payload),
+            TEST_DATA.as_bytes().to_vec()
+        );
+        assert_eq!(echo(&LmcpSentinelizer, payload), payload.to_vec());
+        let custom = SentinelConfig::new()
+            .before_payload_size(b"<<")
+            .build()
+            .unwrap();
+        assert_eq!(echo(&custom, payload), payload.to_vec());
+
+        assert_eq!(Identity.create(payload), payload.to_vec());
+        assert_eq!(echo(&Identity, payload), payload.to_vec());
+    }
+}
@@ -0,0 +1,75 @@
+//! Async stream of payloads read from a tokio `AsyncRead`, e.g. a `TcpStream`
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{SentinelDecoder, Sentinelizer};
+
+/// Size of the chunks read from the reader
+const READ_CHUNK_LEN: usize = 8 * 1024;
+
+/// Reads sentinelized bytes from `R` and yields the decoded payloads.
+///
+/// The reader is only polled when the buffered bytes don't contain another frame,
+/// so a consumer that stops polling stops the reads as well. Corrupt frames are
+/// yielded as `InvalidData` errors and skipped, the stream continues with the next
+/// frame. It ends when the reader reaches EOF, with an `UnexpectedEof` error first
+/// if the reader stopped in the middle of a frame. Errors of the reader itself are
+/// passed on, which is why the items are `io::Result`s rather than `Error`s.
+#[derive(Debug)]
+pub struct SentinelStream<R> {
+    reader: R,
+    decoder: SentinelDecoder,
+    chunk: Box<[u8]>,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> SentinelStream<R> {
+    /// Decode frames with the default sentinels from the reader
+    pub fn new(reader: R) -> Self {
+        Self::with_sentinelizer(reader, Sentinelizer::default())
+    }
+
+    /// Decode frames using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(reader: R, sentinelizer: Sentinelizer) -> Self {
+        SentinelStream {
+            reader,
+            decoder: SentinelDecoder::with_sentinelizer(sentinelizer),
+            chunk: vec![0; READ_CHUNK_LEN].into_boxed_slice(),
+            eof: false,
+        }
+    }
+
+    /// Return the underlying reader, dropping any buffered bytes
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for SentinelStream<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(msg) = this.decoder.next_message() {
+                return Poll::Ready(Some(msg.map_err(io::Error::from)));
+            }
+            if this.eof {
+                return Poll::Ready(this.decoder.finish().map(|e| Err(e.into())));
+            }
+
+            let mut buf = ReadBuf::new(&mut this.chunk);
+            match Pin::new(&mut this.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => this.eof = true,
+                Poll::Ready(Ok(())) => this.decoder.push_bytes(buf.filled()),
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
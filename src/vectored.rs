@@ -0,0 +1,89 @@
+//! Zero-copy framing for vectored writes: the payload is sent from the caller's
+//! buffer, only the numeric fields have to be formatted
+
+use std::io::IoSlice;
+
+use super::Sentinelizer;
+
+/// Holds the formatted length field and trailer referenced by the slices of
+/// `Sentinelizer::sentinel_io_slices`. It can be reused for every frame, the
+/// buffers keep their capacity.
+#[derive(Debug, Clone, Default)]
+pub struct SentinelScratch {
+    len: Vec<u8>,
+    trailer: Vec<u8>,
+}
+
+impl SentinelScratch {
+    /// Create empty buffers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Format the numeric fields of the frame for `payload`
+    pub(crate) fn fill(&mut self, sentinelizer: &Sentinelizer, payload: &[u8]) {
+        self.len.clear();
+        self.len
+            .extend_from_slice(sentinelizer.numeric_field(payload.len() as u64).as_bytes());
+        self.trailer.clear();
+        sentinelizer.write_trailer(sentinelizer.checksum(payload), &mut self.trailer);
+    }
+
+    /// The formatted length field
+    pub(crate) fn len_field(&self) -> &[u8] {
+        &self.len
+    }
+
+    /// The formatted trailer
+    pub(crate) fn trailer(&self) -> &[u8] {
+        &self.trailer
+    }
+}
+
+impl Sentinelizer {
+    /// Slices of the frame for `payload`: the header sentinel, the length digits,
+    /// the sentinel after them, the payload and the trailer, to pass to
+    /// `write_vectored`. The numeric fields are formatted into `scratch`.
+    pub fn sentinel_io_slices<'a>(
+        &'a self,
+        payload: &'a [u8],
+        scratch: &'a mut SentinelScratch,
+    ) -> [IoSlice<'a>; 5] {
+        scratch.fill(self, payload);
+        [
+            IoSlice::new(self.before_payload_size_sentinel()),
+            IoSlice::new(&scratch.len),
+            IoSlice::new(self.after_payload_size_sentinel()),
+            IoSlice::new(payload),
+            IoSlice::new(&scratch.trailer),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LmcpSentinelizer;
+    use std::io::Write;
+
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_write_vectored() {
+        let sentinelizer = Sentinelizer::default();
+        let mut scratch = SentinelScratch::new();
+        for payload in &[TEST_PAYLOAD.as_bytes(), &[], &[0xFF; 1000]] {
+            let expected = LmcpSentinelizer::create_sentinelized_stream(payload);
+
+            let slices = sentinelizer.sentinel_io_slices(payload, &mut scratch);
+            let mut out = vec![];
+            assert_eq!(out.write_vectored(&slices).unwrap(), expected.len());
+            assert_eq!(out, expected);
+
+            let slices = LmcpSentinelizer::sentinel_io_slices(payload, &mut scratch);
+            let mut out = vec![];
+            assert_eq!(out.write_vectored(&slices).unwrap(), expected.len());
+            assert_eq!(out, expected);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Framing of payloads written through `std::io::Write`, the counterpart of
+//! `FrameReader`, so code producing a payload with `write!` or a serializer
+//! writing to a `Write` gets framed output without changes
+
+use std::io::{self, Write};
+use std::mem;
+
+use super::{SentinelEncoder, Sentinelizer};
+
+/// Frames the bytes written to it and forwards the frames to `W`.
+///
+/// Everything written between two calls to `flush` is one payload: the writes
+/// are collected with a `SentinelEncoder`, as the header needs the length, and
+/// `flush` writes the frame to the underlying writer and flushes it. Individual
+/// `write`s don't delimit payloads, as `write_all` and formatting split them
+/// arbitrarily. A flush without any bytes written since the last one writes no
+/// frame, so empty payloads have to be framed with `write_to` instead.
+///
+/// Bytes that haven't been flushed are lost when the writer is dropped, use
+/// `into_inner` to write the last payload and get errors doing so.
+#[derive(Debug)]
+pub struct SentinelWriter<W: Write> {
+    writer: W,
+    sentinelizer: Sentinelizer,
+    encoder: SentinelEncoder,
+}
+
+impl<W: Write> SentinelWriter<W> {
+    /// Frame payloads with the default sentinels
+    pub fn new(writer: W) -> Self {
+        Self::with_sentinelizer(writer, Sentinelizer::default())
+    }
+
+    /// Frame payloads using the sentinels of a configured sentinelizer
+    pub fn with_sentinelizer(writer: W, sentinelizer: Sentinelizer) -> Self {
+        SentinelWriter {
+            writer,
+            encoder: sentinelizer.encoder(),
+            sentinelizer,
+        }
+    }
+
+    /// Number of payload bytes written since the last flush
+    pub fn pending_len(&self) -> usize {
+        self.encoder.len()
+    }
+
+    /// Write the pending payload, if any, and return the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Write for SentinelWriter<W> {
+    /// Append the bytes to the pending payload, this never fails
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoder.update(buf);
+        Ok(buf.len())
+    }
+
+    /// Write the pending payload as one frame and flush the underlying writer.
+    /// If writing fails, the payload is dropped and the frame may be cut off.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.encoder.is_empty() {
+            let encoder = mem::replace(&mut self.encoder, self.sentinelizer.encoder());
+            encoder.finish(&mut self.writer)?;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LmcpSentinelizer, SentinelConfig};
+
+    const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+    #[test]
+    fn test_two_payloads() {
+        let mut writer = SentinelWriter::new(Vec::new());
+        writer.write_all(b"ABCDEFGHIJKL").unwrap();
+        write!(writer, "MNOPQRSTUVWXY").unwrap();
+        assert_eq!(writer.pending_len(), TEST_PAYLOAD.len());
+        writer.flush().unwrap();
+        assert_eq!(writer.pending_len(), 0);
+        // nothing pending, no frame
+        writer.flush().unwrap();
+        write!(writer, "{}", 42).unwrap();
+        let out = writer.into_inner().unwrap();
+
+        assert!(out.starts_with(TEST_DATA.as_bytes()));
+        assert_eq!(
+            LmcpSentinelizer::parse_all(&out),
+            vec![Ok(TEST_PAYLOAD.as_bytes().to_vec()), Ok(b"42".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_configured() {
+        let sentinelizer = SentinelConfig::new()
+            .before_payload_size(b"<<")
+            .build()
+            .unwrap();
+        let mut writer = SentinelWriter::with_sentinelizer(Vec::new(), sentinelizer.clone());
+        writer.write_all(TEST_PAYLOAD.as_bytes()).unwrap();
+        let out = writer.into_inner().unwrap();
+        assert_eq!(
+            out,
+            sentinelizer.create_sentinelized_stream(TEST_PAYLOAD.as_bytes())
+        );
+    }
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "tokio")]
+
+use futures::{SinkExt, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{Framed, FramedRead};
+
+use lmcp_sentinelizer::{LmcpSentinelizer, SentinelCodec};
+
+const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+#[tokio::test]
+async fn test_framed_round_trip() {
+    let (a, b) = tokio::io::duplex(16);
+    let mut tx = Framed::new(a, SentinelCodec::new());
+    let mut rx = Framed::new(b, SentinelCodec::new());
+
+    let messages = vec![
+        b"ABCDEFGHIJKLMNOPQRSTUVWXY".to_vec(),
+        vec![],
+        vec![0xFF; 1000],
+    ];
+
+    let sent = messages.clone();
+    let writer = tokio::spawn(async move {
+        for msg in sent {
+            tx.send(msg).await.unwrap();
+        }
+    });
+
+    for msg in messages {
+        assert_eq!(rx.next().await.unwrap().unwrap(), msg);
+    }
+    writer.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_corrupt_frame_doesnt_end_stream() {
+    let (mut a, b) = tokio::io::duplex(16);
+    let mut rx = FramedRead::new(b, SentinelCodec::new());
+
+    let writer = tokio::spawn(async move {
+        a.write_all(TEST_DATA.replace("1925", "1926").as_bytes())
+            .await
+            .unwrap();
+        a.write_all(&LmcpSentinelizer::create_sentinelized_stream(b"x"))
+            .await
+            .unwrap();
+        a.write_all(TEST_DATA.as_bytes()).await.unwrap();
+    });
+
+    assert_eq!(rx.next().await.unwrap().unwrap(), b"x");
+    assert_eq!(rx.next().await.unwrap().unwrap(), TEST_PAYLOAD.as_bytes());
+    writer.await.unwrap();
+    assert!(rx.next().await.is_none());
+}
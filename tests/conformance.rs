@@ -0,0 +1,59 @@
+//! Frames must be byte identical to the output of `UxAS_SentinelSerialBuffer`.
+//! The expected frames follow `createSentinelizedString`: the length and the
+//! checksum are stringified with `std::to_string`, i.e. plain decimal without
+//! padding, and `calculateChecksum` sums the payload bytes as unsigned values
+//! into a `uint32_t` that wraps around.
+
+use lmcp_sentinelizer::LmcpSentinelizer;
+
+const VECTORS: &[(&[u8], &[u8])] = &[
+    (b"", b"+=+=+=+=0#@#@#@#@!%!%!%!%0?^?^?^?^"),
+    (b"A", b"+=+=+=+=1#@#@#@#@A!%!%!%!%65?^?^?^?^"),
+    (
+        b"0123456789",
+        b"+=+=+=+=10#@#@#@#@0123456789!%!%!%!%525?^?^?^?^",
+    ),
+    (
+        b"ABCDEFGHIJKLMNOPQRSTUVWXY",
+        b"+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^",
+    ),
+    // bytes with the high bit set count as 128..=255, not as negative chars
+    (
+        b"\x00\x7f\x80\xff",
+        b"+=+=+=+=4#@#@#@#@\x00\x7f\x80\xff!%!%!%!%510?^?^?^?^",
+    ),
+];
+
+#[test]
+fn test_conformance_vectors() {
+    for &(payload, frame) in VECTORS {
+        assert_eq!(
+            LmcpSentinelizer::create_sentinelized_stream(payload),
+            frame.to_vec()
+        );
+        assert_eq!(LmcpSentinelizer::encoded_len(payload), frame.len());
+
+        let (parsed, rem) = LmcpSentinelizer::parse_sentinelized_stream(frame.to_vec()).unwrap();
+        assert_eq!(parsed, payload.to_vec());
+        assert!(rem.is_empty());
+    }
+}
+
+#[test]
+fn test_conformance_checksum_overflow() {
+    // 16843010 * 255 = 2^32 + 254
+    let payload = vec![0xffu8; 16_843_010];
+    let frame = LmcpSentinelizer::create_sentinelized_stream(&payload);
+
+    let header = b"+=+=+=+=16843010#@#@#@#@";
+    let trailer = b"!%!%!%!%254?^?^?^?^";
+    assert_eq!(frame.len(), header.len() + payload.len() + trailer.len());
+    assert_eq!(&frame[..header.len()], &header[..]);
+    assert_eq!(&frame[frame.len() - trailer.len()..], &trailer[..]);
+    assert!(frame[header.len()..frame.len() - trailer.len()]
+        .iter()
+        .all(|&b| b == 0xff));
+
+    let (parsed, _) = LmcpSentinelizer::parse_one(&frame).unwrap();
+    assert_eq!(parsed, payload);
+}
@@ -0,0 +1,25 @@
+//! Smoke test for the core API of a `no_std` build,
+//! run with `cargo test --no-default-features`
+#![cfg(not(feature = "std"))]
+
+use lmcp_sentinelizer::{LmcpSentinelizer, SentinelDecoder};
+
+const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+#[test]
+fn test_no_std_round_trip() {
+    let frame = LmcpSentinelizer::create_sentinelized_stream(TEST_PAYLOAD.as_bytes());
+    assert_eq!(frame, TEST_DATA.as_bytes().to_vec());
+
+    let (payload, rem) = LmcpSentinelizer::parse_sentinelized_stream(frame).unwrap();
+    assert_eq!(payload, TEST_PAYLOAD.as_bytes().to_vec());
+    assert!(rem.is_empty());
+
+    let mut decoder = SentinelDecoder::new();
+    decoder.push_bytes(TEST_DATA.as_bytes());
+    assert_eq!(
+        decoder.next_message(),
+        Some(Ok(TEST_PAYLOAD.as_bytes().to_vec()))
+    );
+}
@@ -0,0 +1,108 @@
+//! Inputs that made the parsers panic at some point, replayed through every parse
+//! entry point. Each must be rejected with an error; a panic fails the test.
+
+use lmcp_sentinelizer::{Error, LmcpSentinelizer, SentinelDecoder};
+
+const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+/// Feed the data to all parsers, return the error of `parse_one`
+fn rejected(data: &[u8]) -> Error {
+    assert!(LmcpSentinelizer::parse_sentinelized_stream(data.to_vec()).is_err());
+    assert!(LmcpSentinelizer::parse_frame(data).is_err());
+    assert!(LmcpSentinelizer::parse_lenient(data).is_err());
+    assert!(LmcpSentinelizer::validate(data).is_err());
+    assert_eq!(LmcpSentinelizer::parse_stream(data.to_vec()).0, None);
+    let _ = LmcpSentinelizer::parse_all(data);
+    let _ = LmcpSentinelizer::parse_resync(data);
+
+    let mut decoder = SentinelDecoder::new();
+    decoder.push_bytes(data);
+    while let Some(msg) = decoder.next_message() {
+        assert!(msg.is_err());
+    }
+    LmcpSentinelizer::parse_one(data).unwrap_err()
+}
+
+/// Indexed past the end of buffers shorter than a sentinel
+#[test]
+fn test_short_buffer() {
+    for data in &[&b""[..], b"+", b"+=+=", b"+=+=+=+", b"+=+=+=+=2"] {
+        assert!(matches!(rejected(data), Error::Incomplete { .. }));
+    }
+}
+
+/// Sliced the payload out of a buffer that ends before the declared length
+#[test]
+fn test_payload_beyond_buffer() {
+    let data = b"+=+=+=+=25#@#@#@#@ABCDE";
+    assert_eq!(rejected(data), Error::Incomplete { needed: Some(37) });
+}
+
+/// `"".parse().unwrap()` on a length field without digits
+#[test]
+fn test_empty_numeric_field() {
+    let data = b"+=+=+=+=#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+    assert!(matches!(rejected(data), Error::MalformedFrame { .. }));
+    let data = TEST_DATA.replace("1925", "");
+    assert!(matches!(
+        rejected(data.as_bytes()),
+        Error::MalformedFrame { .. }
+    ));
+}
+
+/// `parse().unwrap()` on a length field with other bytes than digits
+#[test]
+fn test_non_digit_numeric_field() {
+    let data = TEST_DATA.replace("25", "x5");
+    assert_eq!(rejected(data.as_bytes()), Error::InvalidNumericField);
+    // numeric chars that aren't ASCII digits weren't valid UTF-8 as single bytes
+    let data = [&b"+=+=+=+=\xb2\xb5"[..], &TEST_DATA.as_bytes()[10..]].concat();
+    assert_eq!(rejected(&data), Error::InvalidNumericField);
+}
+
+/// Overflowed the integer type of the fields, and scanned arbitrarily long digit runs
+#[test]
+fn test_digit_bomb() {
+    let data = TEST_DATA.replace("25", &"9".repeat(10_000));
+    assert_eq!(rejected(data.as_bytes()), Error::InvalidNumericField);
+    let data = TEST_DATA.replace("1925", &"9".repeat(10_000));
+    assert_eq!(rejected(data.as_bytes()), Error::InvalidNumericField);
+}
+
+/// Overflowed the index of the payload end for lengths close to `usize::MAX`
+#[test]
+fn test_length_overflow() {
+    let data = TEST_DATA.replace("25", &u64::MAX.to_string());
+    assert_eq!(rejected(data.as_bytes()), Error::PayloadTooLarge);
+}
+
+/// Truncated checksum fields that don't fit into `u32`
+#[test]
+fn test_checksum_beyond_u32() {
+    let data = TEST_DATA.replace("1925", &(u64::from(u32::MAX) + 1925).to_string());
+    assert_eq!(rejected(data.as_bytes()), Error::InvalidNumericField);
+}
+
+/// Overflowed the checksum accumulator in debug builds
+#[test]
+fn test_checksum_overflow() {
+    // 16843010 * 255 = 2^32 + 254
+    let payload = vec![0xff; 16_843_010];
+    let frame = LmcpSentinelizer::create_sentinelized_stream(&payload);
+    assert!(frame.ends_with(b"!%!%!%!%254?^?^?^?^"));
+    assert_eq!(LmcpSentinelizer::parse_one(&frame).unwrap().0, payload);
+
+    let mut corrupted = frame;
+    let len = corrupted.len();
+    corrupted[len - 9] = b'5';
+    assert_eq!(rejected(&corrupted), Error::ChecksumVerifyError);
+}
+
+/// Valid frames still parse after the fixes
+#[test]
+fn test_valid_frame() {
+    let (payload, consumed) = LmcpSentinelizer::parse_one(TEST_DATA.as_bytes()).unwrap();
+    assert_eq!(payload, TEST_PAYLOAD.as_bytes());
+    assert_eq!(consumed, TEST_DATA.len());
+}
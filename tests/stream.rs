@@ -0,0 +1,58 @@
+#![cfg(feature = "tokio")]
+
+use std::io;
+
+use futures::StreamExt;
+use tokio::io::AsyncWriteExt;
+
+use lmcp_sentinelizer::{LmcpSentinelizer, SentinelStream};
+
+const TEST_DATA: &str = "+=+=+=+=25#@#@#@#@ABCDEFGHIJKLMNOPQRSTUVWXY!%!%!%!%1925?^?^?^?^";
+const TEST_PAYLOAD: &str = "ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+#[tokio::test]
+async fn test_fragmented_frames() {
+    let (mut tx, rx) = tokio::io::duplex(8);
+    let mut data = TEST_DATA.repeat(2).into_bytes();
+    data.extend_from_slice(&LmcpSentinelizer::create_sentinelized_stream(&[0xff; 100]));
+    data.extend_from_slice(b"garbage");
+    data.extend_from_slice(TEST_DATA.as_bytes());
+    data.extend_from_slice(&TEST_DATA.as_bytes()[..20]);
+
+    let writer = tokio::spawn(async move {
+        for chunk in data.chunks(5) {
+            tx.write_all(chunk).await.unwrap();
+        }
+    });
+
+    let mut stream = SentinelStream::new(rx);
+    assert_eq!(
+        stream.next().await.unwrap().unwrap(),
+        TEST_PAYLOAD.as_bytes()
+    );
+    assert_eq!(
+        stream.next().await.unwrap().unwrap(),
+        TEST_PAYLOAD.as_bytes()
+    );
+    assert_eq!(stream.next().await.unwrap().unwrap(), vec![0xff; 100]);
+
+    // the garbage may arrive in several reads, each reported separately
+    let mut errors = 0;
+    let payload = loop {
+        match stream.next().await.unwrap() {
+            Ok(payload) => break payload,
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+                errors += 1;
+            }
+        }
+    };
+    assert!(errors > 0);
+    assert_eq!(payload, TEST_PAYLOAD.as_bytes());
+
+    // the writer is dropped in the middle of the last frame
+    let e = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    assert!(stream.next().await.is_none());
+    writer.await.unwrap();
+}